@@ -0,0 +1,84 @@
+//! Benchmarks denoising a fixed size noisy image on the CPU device.
+//!
+//! Run with `cargo bench`, the results are only meaningful when linking the
+//! real Open Image Denoise library rather than the `stub` feature.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use oidn::{Device, FrameDenoiser, RayTracing};
+
+const WIDTH: usize = 256;
+const HEIGHT: usize = 256;
+
+/// A deterministic noisy gradient, so runs are comparable
+fn noisy_image(seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..3 * WIDTH * HEIGHT)
+        .map(|i| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (state >> 8) as f32 / (1 << 24) as f32;
+            let gradient = (i / 3 % WIDTH) as f32 / WIDTH as f32;
+            0.5 * gradient + 0.5 * noise
+        })
+        .collect()
+}
+
+fn filter(c: &mut Criterion) {
+    let device = Device::cpu();
+    let color = noisy_image(1);
+    let mut output = vec![0.0; color.len()];
+    let mut filter = RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    c.bench_function("filter", |b| {
+        b.iter(|| filter.filter(&color, &mut output).unwrap())
+    });
+}
+
+fn buffer_reuse(c: &mut Criterion) {
+    let device = Device::cpu();
+    let color = noisy_image(1);
+    let mut output = vec![0.0; color.len()];
+
+    let color_buffer = device.create_buffer(&color).unwrap();
+    let mut output_buffer = device.create_buffer(&output).unwrap();
+    let mut filter = RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    c.bench_function("filter_buffer", |b| {
+        b.iter(|| {
+            filter
+                .filter_buffer(&color_buffer, &mut output_buffer)
+                .unwrap()
+        })
+    });
+
+    let mut filter = RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    let mut denoiser = FrameDenoiser::new(filter).unwrap();
+    c.bench_function("frame_denoiser", |b| {
+        b.iter(|| denoiser.denoise_into(&color, &mut output).unwrap())
+    });
+}
+
+fn aux_images(c: &mut Criterion) {
+    let device = Device::cpu();
+    let color = noisy_image(1);
+    let albedo = noisy_image(2);
+    let normal = noisy_image(3);
+    let mut output = vec![0.0; color.len()];
+
+    let mut filter = RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT).albedo(&albedo);
+    c.bench_function("filter_albedo", |b| {
+        b.iter(|| filter.filter(&color, &mut output).unwrap())
+    });
+
+    let mut filter = RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo_normal(&albedo, &normal);
+    c.bench_function("filter_albedo_normal", |b| {
+        b.iter(|| filter.filter(&color, &mut output).unwrap())
+    });
+}
+
+criterion_group!(benches, filter, buffer_reuse, aux_images);
+criterion_main!(benches);
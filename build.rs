@@ -1,17 +1,218 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+fn target_os() -> String {
+    env::var("CARGO_CFG_TARGET_OS").unwrap_or_default()
+}
+
+/// Link the static OIDN libraries, enabled by the `static` feature
+fn link_static() -> bool {
+    env::var("CARGO_FEATURE_STATIC").is_ok()
+}
+
+/// Install prefixes searched when neither `OIDN_DIR` nor `OIDN_LIB_DIR` is
+/// set
+fn default_prefixes() -> Vec<PathBuf> {
+    if target_os() == "windows" {
+        vec![
+            PathBuf::from(r"C:\Program Files\Intel\OpenImageDenoise"),
+            PathBuf::from(r"C:\Program Files\OpenImageDenoise"),
+        ]
+    } else {
+        vec![
+            PathBuf::from("/usr/local"),
+            PathBuf::from("/usr"),
+            PathBuf::from("/opt/homebrew"),
+            PathBuf::from("/opt/OpenImageDenoise"),
+        ]
+    }
+}
+
+/// Check if `dir` contains the library `name` to link against
+fn has_lib(dir: &Path, name: &str) -> bool {
+    let files = match target_os().as_str() {
+        "windows" => vec![format!("{name}.lib")],
+        _ if link_static() => vec![format!("lib{name}.a")],
+        "macos" => vec![format!("lib{name}.dylib"), format!("lib{name}.a")],
+        _ => vec![format!("lib{name}.so"), format!("lib{name}.a")],
+    };
+    files.iter().any(|file| dir.join(file).exists())
+}
+
+fn has_oidn_lib(dir: &Path) -> bool {
+    has_lib(dir, "OpenImageDenoise")
+}
+
+/// Link OIDN and the runtime libraries shipped alongside it. OIDN 2.x splits
+/// into `OpenImageDenoise` and `OpenImageDenoise_core`, and the release
+/// packages bundle TBB. Static builds also contain the CPU device library,
+/// which is otherwise loaded at runtime.
+fn link_libs(dir: &Path) {
+    let kind = if link_static() { "static=" } else { "" };
+    println!("cargo:rustc-link-lib={kind}OpenImageDenoise");
+    if has_lib(dir, "OpenImageDenoise_core") {
+        println!("cargo:rustc-link-lib={kind}OpenImageDenoise_core");
+    }
+    if link_static() && has_lib(dir, "OpenImageDenoise_device_cpu") {
+        println!("cargo:rustc-link-lib={kind}OpenImageDenoise_device_cpu");
+    }
+    if let Some(tbb) = ["tbb", "tbb12"].into_iter().find(|tbb| has_lib(dir, tbb)) {
+        println!("cargo:rustc-link-lib={kind}{tbb}");
+    }
+    if link_static() {
+        // OIDN is written in C++, so its runtime must be linked as well
+        match target_os().as_str() {
+            "macos" | "ios" => println!("cargo:rustc-link-lib=c++"),
+            "windows" => {}
+            _ => println!("cargo:rustc-link-lib=stdc++"),
+        }
+        return;
+    }
+    // Let the crate's tests and dependents (via DEP_OPENIMAGEDENOISE_LIB_DIR)
+    // find the shared libraries at runtime without setting LD_LIBRARY_PATH
+    println!("cargo:lib_dir={}", dir.display());
+    if target_os() != "windows" {
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", dir.display());
+    }
+}
+
+/// Check if `dir` contains the CMake package config installed by OIDN, e.g.
+/// `lib/cmake/OpenImageDenoise-2.3.1`
+fn has_cmake_config(dir: &Path) -> bool {
+    dir.join("cmake")
+        .read_dir()
+        .map(|entries| {
+            entries.flatten().any(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("OpenImageDenoise")
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Find the directory containing the OIDN library, recording every path
+/// that was checked in `searched`. Explicitly set directories are trusted
+/// even if the library can't be found in them.
+fn find_lib_dir(searched: &mut Vec<PathBuf>) -> Option<PathBuf> {
+    if let Ok(dir) = env::var("OIDN_LIB_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Ok(dir) = env::var("OIDN_DIR") {
+        let prefix = PathBuf::from(dir);
+        let lib64 = prefix.join("lib64");
+        if !has_oidn_lib(&prefix.join("lib")) && has_oidn_lib(&lib64) {
+            return Some(lib64);
+        }
+        return Some(prefix.join("lib"));
+    }
+    for prefix in default_prefixes() {
+        for lib in ["lib", "lib64"] {
+            let dir = prefix.join(lib);
+            searched.push(dir.clone());
+            if has_oidn_lib(&dir) || has_cmake_config(&dir) {
+                return Some(dir);
+            }
+        }
+    }
+    None
+}
+
+/// Reads the major version of the OIDN headers in `include_dir` from
+/// `OpenImageDenoise/config.h`, if they're installed there
+fn header_major_version(include_dir: &Path) -> Option<u32> {
+    let config = std::fs::read_to_string(include_dir.join("OpenImageDenoise/config.h")).ok()?;
+    config.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("#define OIDN_VERSION_MAJOR")?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
+
+/// Generates the raw bindings from the OIDN headers in `include_dir` into
+/// `$OUT_DIR/sys.rs`, with the same options as
+/// `scripts/generate-sys-bindings.sh`. Without headers to generate them from
+/// (e.g. with the `stub` feature) the checked-in bindings are used.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(include_dir: Option<&Path>) {
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("sys.rs");
+    let header = include_dir
+        .map(|dir| dir.join("OpenImageDenoise/oidn.h"))
+        .filter(|header| header.exists());
+    let Some(header) = header else {
+        std::fs::copy("src/sys.rs", &out).expect("Failed to copy the checked-in bindings");
+        return;
+    };
+    println!("cargo:rerun-if-changed={}", header.display());
+    bindgen::Builder::default()
+        .header(header.to_string_lossy())
+        .clang_arg(format!("-I{}", include_dir.unwrap().display()))
+        .generate_comments(false)
+        .trust_clang_mangling(false)
+        .allowlist_function("oidn.*")
+        .allowlist_type("OIDN.*")
+        .generate()
+        .expect("Failed to generate the OpenImageDenoise bindings")
+        .write_to_file(&out)
+        .expect("Failed to write the OpenImageDenoise bindings");
+}
 
 fn main() {
-    if env::var("DOCS_RS").is_err() {
-        if let Ok(e) = env::var("OIDN_DIR") {
-            let mut oidn_dir = PathBuf::from(e);
-            oidn_dir.push("lib");
-            println!("cargo:rustc-link-search=native={}", oidn_dir.display());
-        } else {
-            println!("cargo:error=Please set OIDN_DIR=<path to OpenImageDenoise install root>");
-            panic!("Failed to find OpenImageDenoise");
+    println!("cargo:rerun-if-env-changed=OIDN_DIR");
+    println!("cargo:rerun-if-env-changed=OIDN_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=OIDN_INCLUDE_DIR");
+    #[cfg(feature = "bindgen")]
+    let mut bindings_include_dir = None;
+    if env::var("DOCS_RS").is_err() && env::var("CARGO_FEATURE_STUB").is_err() {
+        let mut searched = Vec::new();
+        match find_lib_dir(&mut searched) {
+            Some(dir) => {
+                println!("cargo:rustc-link-search=native={}", dir.display());
+                let include_dir = env::var("OIDN_INCLUDE_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| dir.parent().unwrap().join("include"));
+                println!("cargo:include={}", include_dir.display());
+                // The bindings in sys.rs are generated from the OIDN 2.x
+                // headers, and the 1.x API differs in function names and
+                // signatures, so linking against 1.x would fail or misbehave
+                match header_major_version(&include_dir) {
+                    Some(2) => {}
+                    Some(major) => panic!(
+                        "Found OpenImageDenoise {major}.x in {}, but this crate requires \
+                         OpenImageDenoise 2.x. Use oidn 1.x for OpenImageDenoise 1.x.",
+                        include_dir.display()
+                    ),
+                    None => println!(
+                        "cargo:warning=Could not read the OpenImageDenoise version from {}, \
+                         make sure OpenImageDenoise 2.x is installed or set OIDN_INCLUDE_DIR",
+                        include_dir.join("OpenImageDenoise/config.h").display()
+                    ),
+                }
+                #[cfg(feature = "bindgen")]
+                {
+                    bindings_include_dir = Some(include_dir);
+                }
+                link_libs(&dir);
+            }
+            None => {
+                println!(
+                    "cargo:warning=Could not find OpenImageDenoise, searched: {}",
+                    searched
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                println!(
+                    "cargo:error=Please set OIDN_DIR=<path to OpenImageDenoise install root> \
+                     or OIDN_LIB_DIR=<path to the directory containing the library>"
+                );
+                panic!("Failed to find OpenImageDenoise");
+            }
         }
-        println!("cargo:rerun-if-env-changed=OIDN_DIR");
-        println!("cargo:rustc-link-lib=OpenImageDenoise");
     }
+    #[cfg(feature = "bindgen")]
+    generate_bindings(bindings_include_dir.as_deref());
 }
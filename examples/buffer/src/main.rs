@@ -27,7 +27,7 @@ fn main() {
         }
         println!();
     }
-    let device = oidn::Device::new();
+    let device = oidn::Device::from_env();
     let mut filter = oidn::filter::RayTracing::new(&device);
     let buffer = device.create_buffer(&input).unwrap();
     let mut output_buffer = device.create_buffer(&[0.0; BUFFER_LEN]).unwrap();
@@ -35,13 +35,11 @@ fn main() {
         .image_dimensions(WIDTH, HEIGHT)
         .filter_buffer(&buffer, &mut output_buffer)
         .unwrap();
-    let slice = output_buffer.read();
+    let pixels = output_buffer.read_pixels::<3>();
     println!();
     println!("denoised:");
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
-            let idx = ((y * WIDTH) + x) * 3;
-            let colour = &slice[idx..idx + 3];
+    for row in pixels.chunks(WIDTH) {
+        for colour in row {
             print!(
                 "\x1b[38;2;{};{};{}m#\x1b[0m",
                 (colour[0] * 255.0) as u8,
@@ -109,7 +109,7 @@ fn main() {
 
     let mut color = load_exr(&args.flag_c);
 
-    let device = oidn::Device::new();
+    let device = oidn::Device::from_env();
 
     let albedo: EXRData;
     let normal: EXRData;
@@ -1,23 +1,285 @@
+use crate::device::device_error;
 use crate::sys::{
-    oidnGetBufferSize, oidnNewBuffer, oidnReadBuffer, oidnReleaseBuffer, oidnWriteBuffer,
-    OIDNBuffer,
+    oidnGetBufferData, oidnGetBufferSize, oidnGetBufferStorage, oidnNewBuffer,
+    oidnNewBufferWithStorage, oidnNewSharedBuffer, oidnReadBuffer, oidnReleaseBuffer,
+    oidnRetainBuffer, oidnWriteBuffer, OIDNBuffer, OIDNDevice,
 };
-use crate::Device;
+use crate::{Device, DeviceType, Error, Format, Storage};
+use std::fmt;
+use std::marker::PhantomData;
 use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice::ChunksExact;
 use std::sync::Arc;
 
-pub struct Buffer {
+pub(crate) mod private {
+    use crate::{Buffer, Device, Format};
+
+    pub trait Sealed {}
+
+    pub trait SealedAux<T: super::BufferElement> {
+        /// The image's values if they're in a host slice, for validating them.
+        fn host_slice(&self) -> Option<&[T]>;
+        /// Converts the image to a buffer on `device`, `existing` is the
+        /// filter's current buffer for the image which may be reused.
+        ///
+        /// # Panics
+        /// - if resource creation fails
+        fn into_buffer(self, device: &Device, existing: Option<Buffer<T>>) -> Buffer<T>;
+    }
+
+    pub trait SealedImage {
+        /// Format of an image of `channels` channels of this element type.
+        fn format(channels: usize) -> Format;
+        fn to_f32(self) -> f32;
+    }
+}
+
+/// An element type which can be stored in a [Buffer].
+///
+/// This trait is sealed and implemented for `f32`, `u8` and, with the `half`
+/// feature enabled, `half::f16`.
+pub trait BufferElement: private::Sealed + Copy + Default {}
+
+impl private::Sealed for f32 {}
+impl BufferElement for f32 {}
+
+impl private::Sealed for u8 {}
+impl BufferElement for u8 {}
+
+#[cfg(feature = "half")]
+impl private::Sealed for half::f16 {}
+#[cfg(feature = "half")]
+impl BufferElement for half::f16 {}
+
+/// A [BufferElement] which can be used for the images of a filter, selecting
+/// between 32-bit and, with the `half` feature enabled, 16-bit float images.
+///
+/// This trait is sealed and implemented for `f32` and `half::f16`.
+pub trait ImageElement: BufferElement + private::SealedImage {}
+
+impl private::SealedImage for f32 {
+    fn format(channels: usize) -> Format {
+        Format::float(channels).unwrap()
+    }
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+impl ImageElement for f32 {}
+
+#[cfg(feature = "half")]
+impl private::SealedImage for half::f16 {
+    fn format(channels: usize) -> Format {
+        Format::half(channels).unwrap()
+    }
+    fn to_f32(self) -> f32 {
+        half::f16::to_f32(self)
+    }
+}
+#[cfg(feature = "half")]
+impl ImageElement for half::f16 {}
+
+/// An albedo or normal image which can be passed to
+/// [RayTracing::albedo](crate::RayTracing::albedo) and
+/// [RayTracing::albedo_normal](crate::RayTracing::albedo_normal).
+///
+/// Slices are copied into a buffer owned by the filter, reusing the filter's
+/// previous buffer if it has the same size. [Buffer]s are used directly if
+/// they were created by the filter's device, and copied to it otherwise.
+///
+/// This trait is sealed and implemented for `&[T]`, `&Vec<T>`, `&[T; N]` and
+/// `Buffer<T>`.
+pub trait AuxImage<T: ImageElement>: private::SealedAux<T> {}
+
+fn slice_into_buffer<T: BufferElement>(
+    contents: &[T],
+    device: &Device,
+    existing: Option<Buffer<T>>,
+) -> Buffer<T> {
+    match existing {
+        Some(mut buffer) if buffer.size == contents.len() && device.same_device_as_buf(&buffer) => {
+            buffer
+                .write(contents)
+                .expect("we check if the size is the same already");
+            buffer
+        }
+        _ => device.create_typed_buffer(contents).unwrap(),
+    }
+}
+
+impl<T: ImageElement> private::SealedAux<T> for &[T] {
+    fn host_slice(&self) -> Option<&[T]> {
+        Some(self)
+    }
+    fn into_buffer(self, device: &Device, existing: Option<Buffer<T>>) -> Buffer<T> {
+        slice_into_buffer(self, device, existing)
+    }
+}
+impl<T: ImageElement> AuxImage<T> for &[T] {}
+
+impl<T: ImageElement> private::SealedAux<T> for &Vec<T> {
+    fn host_slice(&self) -> Option<&[T]> {
+        Some(self)
+    }
+    fn into_buffer(self, device: &Device, existing: Option<Buffer<T>>) -> Buffer<T> {
+        slice_into_buffer(self, device, existing)
+    }
+}
+impl<T: ImageElement> AuxImage<T> for &Vec<T> {}
+
+impl<T: ImageElement, const N: usize> private::SealedAux<T> for &[T; N] {
+    fn host_slice(&self) -> Option<&[T]> {
+        Some(*self)
+    }
+    fn into_buffer(self, device: &Device, existing: Option<Buffer<T>>) -> Buffer<T> {
+        slice_into_buffer(self, device, existing)
+    }
+}
+impl<T: ImageElement, const N: usize> AuxImage<T> for &[T; N] {}
+
+impl<T: ImageElement> private::SealedAux<T> for Buffer<T> {
+    fn host_slice(&self) -> Option<&[T]> {
+        None
+    }
+    fn into_buffer(self, device: &Device, _existing: Option<Buffer<T>>) -> Buffer<T> {
+        if device.same_device_as_buf(&self) {
+            return self;
+        }
+        device.create_typed_buffer(&self.read()).unwrap()
+    }
+}
+impl<T: ImageElement> AuxImage<T> for Buffer<T> {}
+
+pub struct Buffer<T: BufferElement = f32> {
     pub(crate) buf: OIDNBuffer,
+    /// Device the buffer was created on, to read errors of buffer operations
+    /// from. OIDN buffers retain their device, so it outlives the buffer.
+    pub(crate) device: OIDNDevice,
     pub(crate) size: usize,
     pub(crate) device_arc: Arc<u8>,
+    pub(crate) marker: PhantomData<T>,
+    /// Host memory shared with OIDN, kept alive for as long as the buffer
+    /// (or a buffer retaining the same handle) exists
+    pub(crate) owned: Option<Arc<Vec<T>>>,
+    /// Width, height and channels of the image stored in the buffer, if known
+    pub(crate) dims: Option<(usize, usize, usize)>,
+}
+
+/// A borrowed range of a [Buffer], e.g. one of several images packed into a
+/// single buffer. Views don't own the buffer's handle, so they can be passed
+/// around freely while the buffer stays alive.
+#[derive(Clone, Copy)]
+pub struct BufferView<'b, T: BufferElement = f32> {
+    buffer: &'b Buffer<T>,
+    offset: usize,
+    len: usize,
+}
+
+impl<'b, T: BufferElement> BufferView<'b, T> {
+    /// The buffer the view borrows from.
+    pub fn buffer(&self) -> &'b Buffer<T> {
+        self.buffer
+    }
+    /// Offset of the view's first element in the buffer.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    /// Number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Reads the view's elements from the buffer.
+    pub fn read(&self) -> Vec<T> {
+        let contents = vec![T::default(); self.len];
+        unsafe {
+            oidnReadBuffer(
+                self.buffer.buf,
+                self.offset * mem::size_of::<T>(),
+                self.len * mem::size_of::<T>(),
+                contents.as_ptr() as *mut _,
+            );
+        }
+        contents
+    }
+    /// Returns the view's elements without copying if the buffer is
+    /// accessible from the host, see [Buffer::as_slice].
+    pub fn as_slice(&self) -> Option<&'b [T]> {
+        let slice = self.buffer.as_slice()?;
+        Some(&slice[self.offset..self.offset + self.len])
+    }
+}
+
+impl<'b, T: BufferElement> From<&'b Buffer<T>> for BufferView<'b, T> {
+    /// A view of the whole buffer.
+    fn from(buffer: &'b Buffer<T>) -> BufferView<'b, T> {
+        BufferView {
+            buffer,
+            offset: 0,
+            len: buffer.size,
+        }
+    }
+}
+
+impl<T: BufferElement> fmt::Debug for BufferView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferView")
+            .field("handle", &self.buffer.buf)
+            .field("offset", &self.offset)
+            .field("len", &self.len)
+            .finish()
+    }
 }
 
 impl Device {
     /// Creates a new buffer from a slice, returns null if buffer creation failed
     pub fn create_buffer(&self, contents: &[f32]) -> Option<Buffer> {
+        self.create_typed_buffer(contents)
+    }
+
+    /// Creates a new buffer holding a `width` x `height` image with `channels`
+    /// elements per pixel, recording its dimensions (see
+    /// [Buffer::dimensions]). Returns [None] if `contents` doesn't have
+    /// `width * height * channels` elements or buffer creation failed.
+    pub fn create_image_buffer<T: BufferElement>(
+        &self,
+        contents: &[T],
+        width: usize,
+        height: usize,
+        channels: usize,
+    ) -> Option<Buffer<T>> {
+        let mut buffer = self.create_typed_buffer(contents)?;
+        buffer.set_dimensions(width, height, channels)?;
+        Some(buffer)
+    }
+
+    /// Creates a new buffer of any [BufferElement] type from a slice, returns
+    /// null if buffer creation failed
+    pub fn create_typed_buffer<T: BufferElement>(&self, contents: &[T]) -> Option<Buffer<T>> {
+        self.create_typed_buffer_with_storage(contents, Storage::Default)
+    }
+
+    /// Creates a new buffer from a slice with the given [Storage] mode,
+    /// returns null if buffer creation failed (e.g. if the device does not
+    /// support the storage mode)
+    pub fn create_buffer_with_storage(&self, contents: &[f32], storage: Storage) -> Option<Buffer> {
+        self.create_typed_buffer_with_storage(contents, storage)
+    }
+
+    /// Creates a new buffer of any [BufferElement] type from a slice with the
+    /// given [Storage] mode, returns null if buffer creation failed
+    pub fn create_typed_buffer_with_storage<T: BufferElement>(
+        &self,
+        contents: &[T],
+        storage: Storage,
+    ) -> Option<Buffer<T>> {
         let byte_size = mem::size_of_val(contents);
         let buffer = unsafe {
-            let buf = oidnNewBuffer(self.0, byte_size);
+            let buf = oidnNewBufferWithStorage(self.0, byte_size, storage.as_raw_oidn_storage());
             if buf.is_null() {
                 return None;
             }
@@ -26,31 +288,266 @@ impl Device {
         };
         Some(Buffer {
             buf: buffer,
+            device: self.0,
             size: contents.len(),
             device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
         })
     }
+
+    /// Creates a new buffer taking ownership of `contents`, returns null if
+    /// buffer creation failed.
+    ///
+    /// On CPU devices the buffer wraps the vector's memory directly instead of
+    /// copying it, halving the host memory needed for large images. Other
+    /// devices copy the contents like [Device::create_buffer].
+    pub fn create_buffer_owned(&self, contents: Vec<f32>) -> Option<Buffer> {
+        self.create_typed_buffer_owned(contents)
+    }
+
+    /// Creates a new buffer of any [BufferElement] type taking ownership of
+    /// `contents`, see [Device::create_buffer_owned].
+    pub fn create_typed_buffer_owned<T: BufferElement>(
+        &self,
+        mut contents: Vec<T>,
+    ) -> Option<Buffer<T>> {
+        if !self.is_cpu() {
+            return self.create_typed_buffer(&contents);
+        }
+        // The vector's heap allocation doesn't move when the vector is moved
+        // into the buffer, so the pointer stays valid for the buffer's lifetime
+        let mut buffer =
+            unsafe { self.create_shared_buffer(contents.as_mut_ptr(), contents.len())? };
+        buffer.owned = Some(Arc::new(contents));
+        Some(buffer)
+    }
+
     /// # Safety
     /// Raw buffer must not be invalid (e.g. destroyed, null ect.)
     ///
     /// Raw buffer must have been created by this device
+    ///
+    /// # Panics
+    /// - if the size of the raw buffer is not a multiple of the size of `f32`
     pub unsafe fn create_buffer_from_raw(&self, buffer: OIDNBuffer) -> Buffer {
-        let size = oidnGetBufferSize(buffer) / mem::size_of::<f32>();
+        self.create_typed_buffer_from_raw(buffer)
+    }
+
+    /// # Safety
+    /// Raw buffer must not be invalid (e.g. destroyed, null ect.)
+    ///
+    /// Raw buffer must have been created by this device
+    ///
+    /// # Panics
+    /// - if the size of the raw buffer is not a multiple of the size of `T`
+    pub unsafe fn create_typed_buffer_from_raw<T: BufferElement>(
+        &self,
+        buffer: OIDNBuffer,
+    ) -> Buffer<T> {
+        let byte_size = oidnGetBufferSize(buffer);
+        let elem = mem::size_of::<T>();
+        // usize::is_multiple_of needs Rust 1.87
+        #[allow(clippy::manual_is_multiple_of)]
+        let whole_elements = byte_size % elem == 0;
+        assert!(
+            whole_elements,
+            "raw buffer holds {byte_size} bytes, which is not a multiple of the \
+             {elem} byte element size"
+        );
+        let size = byte_size / elem;
         Buffer {
             buf: buffer,
+            device: self.0,
             size,
             device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
+        }
+    }
+
+    /// Creates a buffer of `len` elements without initializing its contents
+    pub(crate) fn create_uninit_buffer<T: BufferElement>(&self, len: usize) -> Option<Buffer<T>> {
+        let buf = unsafe { oidnNewBuffer(self.0, len * mem::size_of::<T>()) };
+        if buf.is_null() {
+            return None;
+        }
+        Some(Buffer {
+            buf,
+            device: self.0,
+            size: len,
+            device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
+        })
+    }
+
+    /// Creates a buffer of `len` elements backed by external memory shared
+    /// through the file descriptor `fd`, e.g. memory exported by Vulkan, so
+    /// images can be denoised and then used by the other API without copies.
+    /// Returns [None] if the device can't import the memory, see
+    /// [Device::supports_external_memory] and [Device::get_error].
+    ///
+    /// Open Image Denoise can only import external memory, it can't export
+    /// its own buffers, so the memory must be allocated by the other API.
+    ///
+    /// # Safety
+    /// `fd` must refer to external memory of `memory_type` holding at least
+    /// `len` elements, which must stay valid for the lifetime of the buffer.
+    #[cfg(unix)]
+    pub unsafe fn create_buffer_from_fd<T: BufferElement>(
+        &self,
+        memory_type: crate::ExternalMemoryType,
+        fd: std::os::unix::io::RawFd,
+        len: usize,
+    ) -> Option<Buffer<T>> {
+        let buf = crate::sys::oidnNewSharedBufferFromFD(
+            self.0,
+            memory_type.as_raw_oidn_external_memory_type_flag(),
+            fd,
+            len * mem::size_of::<T>(),
+        );
+        if buf.is_null() {
+            return None;
         }
+        Some(Buffer {
+            buf,
+            device: self.0,
+            size: len,
+            device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
+        })
     }
 
-    pub(crate) fn same_device_as_buf(&self, buf: &Buffer) -> bool {
+    /// Creates a buffer of `len` elements backed by external memory shared
+    /// through the Win32 `handle` or, if `handle` is null, the named object
+    /// `name` (a null terminated UTF-16 string), e.g. memory exported by
+    /// Vulkan or Direct3D. Returns [None] if the device can't import the
+    /// memory, see [Device::supports_external_memory] and [Device::get_error].
+    ///
+    /// # Safety
+    /// `handle` or `name` must refer to external memory of `memory_type`
+    /// holding at least `len` elements, which must stay valid for the lifetime
+    /// of the buffer.
+    #[cfg(windows)]
+    pub unsafe fn create_buffer_from_win32_handle<T: BufferElement>(
+        &self,
+        memory_type: crate::ExternalMemoryType,
+        handle: *mut std::ffi::c_void,
+        name: *const u16,
+        len: usize,
+    ) -> Option<Buffer<T>> {
+        let buf = crate::sys::oidnNewSharedBufferFromWin32Handle(
+            self.0,
+            memory_type.as_raw_oidn_external_memory_type_flag(),
+            handle,
+            name as *const _,
+            len * mem::size_of::<T>(),
+        );
+        if buf.is_null() {
+            return None;
+        }
+        Some(Buffer {
+            buf,
+            device: self.0,
+            size: len,
+            device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
+        })
+    }
+
+    /// Creates a buffer backed by the Metal buffer `mtl_buffer` (an
+    /// `id<MTLBuffer>`), so images rendered with Metal can be denoised in
+    /// place without copies. The buffer's size is taken from the Metal
+    /// buffer. Returns [None] if the device can't use it, e.g. because it is
+    /// not a Metal device, see [Device::get_error].
+    ///
+    /// # Safety
+    /// `mtl_buffer` must be a valid Metal buffer created on the same Metal
+    /// device as this device, and must stay valid for the lifetime of the
+    /// buffer.
+    ///
+    /// # Panics
+    /// - if the size of the Metal buffer is not a multiple of the size of `T`
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub unsafe fn create_buffer_from_metal<T: BufferElement>(
+        &self,
+        mtl_buffer: *mut std::ffi::c_void,
+    ) -> Option<Buffer<T>> {
+        let buf = crate::sys::oidnNewSharedBufferFromMetal(self.0, mtl_buffer);
+        if buf.is_null() {
+            return None;
+        }
+        Some(self.create_typed_buffer_from_raw(buf))
+    }
+
+    /// Wraps `len` elements of host memory starting at `ptr` in a buffer
+    /// without copying. Only valid for devices which can access host memory.
+    ///
+    /// # Safety
+    /// The memory must stay valid for the lifetime of the buffer, and must not
+    /// be written to by the filter unless `ptr` may be mutated.
+    pub(crate) unsafe fn create_shared_buffer<T: BufferElement>(
+        &self,
+        ptr: *mut T,
+        len: usize,
+    ) -> Option<Buffer<T>> {
+        let buf = oidnNewSharedBuffer(self.0, ptr as *mut _, len * mem::size_of::<T>());
+        if buf.is_null() {
+            return None;
+        }
+        Some(Buffer {
+            buf,
+            device: self.0,
+            size: len,
+            device_arc: self.1.clone(),
+            marker: PhantomData,
+            owned: None,
+            dims: None,
+        })
+    }
+
+    /// Shares `buf` with this device if it was created by it, otherwise
+    /// copies its contents into a new buffer on this device
+    pub(crate) fn share_or_copy_buffer<T: BufferElement>(
+        &self,
+        buf: &Buffer<T>,
+    ) -> Option<Buffer<T>> {
+        if self.same_device_as_buf(buf) {
+            unsafe { oidnRetainBuffer(buf.buf) };
+            return Some(Buffer {
+                buf: buf.buf,
+                device: self.0,
+                size: buf.size,
+                device_arc: self.1.clone(),
+                marker: PhantomData,
+                owned: buf.owned.clone(),
+                dims: buf.dims,
+            });
+        }
+        self.create_typed_buffer(&buf.read())
+    }
+
+    pub(crate) fn same_device_as_buf<T: BufferElement>(&self, buf: &Buffer<T>) -> bool {
         self.1.as_ref() as *const _ as isize == buf.device_arc.as_ref() as *const _ as isize
     }
 }
 
-impl Buffer {
+impl<T: BufferElement> Buffer<T> {
+    /// Returns the type of the device which created the buffer.
+    pub fn device_type(&self) -> DeviceType {
+        DeviceType::try_from(*self.device_arc as u32).unwrap_or_default()
+    }
+
     /// Writes to the buffer, returns [None] if the sizes mismatch
-    pub fn write(&mut self, contents: &[f32]) -> Option<()> {
+    pub fn write(&mut self, contents: &[T]) -> Option<()> {
         if self.size != contents.len() {
             return None;
         }
@@ -61,7 +558,7 @@ impl Buffer {
         Some(())
     }
     /// Reads from the buffer to the array, returns [None] if the sizes mismatch
-    pub fn read_to_slice(&mut self, contents: &mut [f32]) -> Option<()> {
+    pub fn read_to_slice(&self, contents: &mut [T]) -> Option<()> {
         if self.size != contents.len() {
             return None;
         }
@@ -72,18 +569,235 @@ impl Buffer {
         Some(())
     }
     /// Reads from the buffer
-    pub fn read(&mut self) -> Vec<f32> {
-        let contents = vec![0.0; self.size];
+    pub fn read(&self) -> Vec<T> {
+        let contents = vec![T::default(); self.size];
         unsafe {
             oidnReadBuffer(
                 self.buf,
                 0,
-                self.size * mem::size_of::<f32>(),
+                self.size * mem::size_of::<T>(),
                 contents.as_ptr() as *mut _,
             );
         }
         contents
     }
+    /// Sets every element of the buffer to `value`, e.g. to clear it before
+    /// accumulating into it. Host accessible buffers (see
+    /// [Buffer::as_slice]) are filled directly, others are written in chunks
+    /// so no host copy of the whole buffer is allocated.
+    ///
+    /// Returns the error reported by the device if writing to the buffer
+    /// failed, the buffer may then be partially filled.
+    pub fn fill(&mut self, value: T) -> Result<(), Error> {
+        if self.size == 0 {
+            return Ok(());
+        }
+        if self.as_slice().is_some() {
+            unsafe {
+                let data = oidnGetBufferData(self.buf) as *mut T;
+                std::slice::from_raw_parts_mut(data, self.size).fill(value);
+            }
+            return Ok(());
+        }
+        const CHUNK_LEN: usize = 64 * 1024;
+        let chunk = vec![value; CHUNK_LEN.min(self.size)];
+        let elem = mem::size_of::<T>();
+        let mut offset = 0;
+        while offset < self.size {
+            let len = chunk.len().min(self.size - offset);
+            unsafe {
+                oidnWriteBuffer(
+                    self.buf,
+                    offset * elem,
+                    len * elem,
+                    chunk.as_ptr() as *const _,
+                );
+            }
+            device_error(self.device).map_err(|(err, _)| err)?;
+            offset += len;
+        }
+        Ok(())
+    }
+    /// Reads the `width` x `height` pixel rectangle at (`x`, `y`) of an image
+    /// `row_width` pixels wide with `channels` elements per pixel, e.g. the
+    /// interior of a denoised tile. Each row of the rectangle is read
+    /// separately, so the rest of the buffer isn't copied.
+    ///
+    /// Returns [None] if the rectangle doesn't fit in the image or the image
+    /// doesn't fit in the buffer.
+    pub fn read_rect(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        row_width: usize,
+        channels: usize,
+    ) -> Option<Vec<T>> {
+        if x.checked_add(width)? > row_width
+            || y.checked_add(height)?
+                .checked_mul(row_width)?
+                .checked_mul(channels)?
+                > self.size
+        {
+            return None;
+        }
+        let row_len = width * channels;
+        let mut contents = vec![T::default(); row_len * height];
+        if row_len == 0 {
+            return Some(contents);
+        }
+        let elem = mem::size_of::<T>();
+        for (row, dst) in contents.chunks_exact_mut(row_len).enumerate() {
+            let offset = ((y + row) * row_width + x) * channels;
+            unsafe {
+                oidnReadBuffer(
+                    self.buf,
+                    offset * elem,
+                    row_len * elem,
+                    dst.as_mut_ptr() as *mut _,
+                );
+            }
+        }
+        Some(contents)
+    }
+    /// Copies `len` elements starting at `src_offset` in `src` to
+    /// `dst_offset` in this buffer.
+    ///
+    /// Open Image Denoise has no API for copying between buffers, so the
+    /// copy is made directly if both buffers are accessible from the host
+    /// (see [Buffer::as_slice]), and through a temporary host copy otherwise.
+    ///
+    /// Returns [Error::InvalidArgument] if the buffers were created by
+    /// different devices or either range is out of bounds.
+    pub fn copy_from(
+        &mut self,
+        src: &Buffer<T>,
+        dst_offset: usize,
+        src_offset: usize,
+        len: usize,
+    ) -> Result<(), Error> {
+        if !Arc::ptr_eq(&self.device_arc, &src.device_arc) {
+            return Err(Error::InvalidArgument);
+        }
+        let in_bounds =
+            |offset: usize, size| offset.checked_add(len).is_some_and(|end| end <= size);
+        if !in_bounds(dst_offset, self.size) || !in_bounds(src_offset, src.size) {
+            return Err(Error::InvalidArgument);
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        let elem = mem::size_of::<T>();
+        unsafe {
+            let dst = oidnGetBufferData(self.buf) as *mut T;
+            match src.as_slice() {
+                Some(src) if !dst.is_null() && self.as_slice().is_some() => {
+                    // ptr::copy as both may be views of the same buffer
+                    ptr::copy(src.as_ptr().add(src_offset), dst.add(dst_offset), len);
+                }
+                _ => {
+                    let mut contents = vec![T::default(); len];
+                    oidnReadBuffer(
+                        src.buf,
+                        src_offset * elem,
+                        len * elem,
+                        contents.as_mut_ptr() as *mut _,
+                    );
+                    oidnWriteBuffer(
+                        self.buf,
+                        dst_offset * elem,
+                        len * elem,
+                        contents.as_ptr() as *const _,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Returns the contents of the buffer without copying if its memory is
+    /// accessible from the host (e.g. buffers on a CPU device or with managed
+    /// storage), or [None] if the buffer lives in device-only memory.
+    pub fn as_slice(&self) -> Option<&[T]> {
+        if self.size == 0 {
+            return Some(&[]);
+        }
+        if !matches!(self.storage(), Storage::Host | Storage::Managed) {
+            return None;
+        }
+        unsafe {
+            let data = oidnGetBufferData(self.buf) as *const T;
+            if data.is_null() {
+                return None;
+            }
+            Some(std::slice::from_raw_parts(data, self.size))
+        }
+    }
+    /// Converts the buffer into a [HostBuffer] which dereferences to its
+    /// elements, or returns it unchanged if its memory isn't accessible from
+    /// the host (see [Buffer::as_slice]).
+    pub fn into_host(self) -> Result<HostBuffer<T>, Buffer<T>> {
+        if self.as_slice().is_none() {
+            return Err(self);
+        }
+        let data = unsafe { oidnGetBufferData(self.buf) as *mut T };
+        Ok(HostBuffer { buffer: self, data })
+    }
+    /// Iterates over the pixels of an image with `channels` channels per
+    /// pixel without copying, or [None] if the buffer lives in device-only
+    /// memory (see [Buffer::as_slice]). Trailing elements which don't make up
+    /// a whole pixel are skipped.
+    ///
+    /// # Panics
+    /// - if `channels` is 0
+    pub fn pixels(&self, channels: usize) -> Option<ChunksExact<'_, T>> {
+        Some(self.as_slice()?.chunks_exact(channels))
+    }
+    /// Reads the buffer as pixels of `N` channels, e.g. `[r, g, b]` triples
+    /// for `N = 3`. Trailing elements which don't make up a whole pixel are
+    /// skipped.
+    ///
+    /// # Panics
+    /// - if `N` is 0
+    pub fn read_pixels<const N: usize>(&self) -> Vec<[T; N]> {
+        self.read()
+            .chunks_exact(N)
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+    /// Borrows `len` elements starting at `offset` as a [BufferView], returns
+    /// [None] if the range is out of bounds.
+    pub fn view(&self, offset: usize, len: usize) -> Option<BufferView<'_, T>> {
+        if offset.checked_add(len)? > self.size {
+            return None;
+        }
+        Some(BufferView {
+            buffer: self,
+            offset,
+            len,
+        })
+    }
+    /// Returns the width, height and channels of the image stored in the
+    /// buffer, if they were recorded with [Buffer::set_dimensions] or the
+    /// buffer was created by [Device::create_image_buffer].
+    pub fn dimensions(&self) -> Option<(usize, usize, usize)> {
+        self.dims
+    }
+    /// Records the dimensions of the image stored in the buffer, which
+    /// filters then check against their image dimensions. Returns [None] if
+    /// they don't match the buffer's size.
+    pub fn set_dimensions(&mut self, width: usize, height: usize, channels: usize) -> Option<()> {
+        if width.checked_mul(height)?.checked_mul(channels)? != self.size {
+            return None;
+        }
+        self.dims = Some((width, height, channels));
+        Some(())
+    }
+    /// Returns the [Storage] mode of the buffer
+    pub fn storage(&self) -> Storage {
+        let storage = unsafe { oidnGetBufferStorage(self.buf) };
+        storage.try_into().unwrap_or_default()
+    }
     /// # Safety
     /// Raw buffer must not be made invalid (e.g. by destroying it)
     pub unsafe fn raw(&self) -> OIDNBuffer {
@@ -92,10 +806,85 @@ impl Buffer {
     pub fn size(&self) -> usize {
         self.size
     }
+    /// Returns the size of the underlying OIDN buffer in bytes, e.g. to check
+    /// the dimensions of an imported raw buffer
+    pub fn byte_size(&self) -> usize {
+        unsafe { oidnGetBufferSize(self.buf) }
+    }
+    /// Returns the number of elements in the buffer, same as [Buffer::size]
+    pub fn len(&self) -> usize {
+        self.size
+    }
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+/// A [Buffer] whose memory is accessible from the host, which dereferences to
+/// a slice of its elements so they can be used without copying.
+///
+/// Created with [Buffer::into_host], which only succeeds for host accessible
+/// buffers (see [Buffer::as_slice]), so device-only buffers can't be
+/// dereferenced.
+pub struct HostBuffer<T: BufferElement = f32> {
+    buffer: Buffer<T>,
+    data: *mut T,
+}
+
+impl<T: BufferElement> HostBuffer<T> {
+    /// The underlying buffer, e.g. to pass it to a filter.
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+    /// The underlying buffer, e.g. to use it as a filter's output.
+    pub fn buffer_mut(&mut self) -> &mut Buffer<T> {
+        &mut self.buffer
+    }
+    pub fn into_inner(self) -> Buffer<T> {
+        self.buffer
+    }
+}
+
+impl<T: BufferElement> Deref for HostBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        if self.buffer.size == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.data, self.buffer.size) }
+    }
 }
 
-impl Drop for Buffer {
+impl<T: BufferElement> DerefMut for HostBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if self.buffer.size == 0 {
+            return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.data, self.buffer.size) }
+    }
+}
+
+impl<T: BufferElement> fmt::Debug for HostBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HostBuffer").field(&self.buffer).finish()
+    }
+}
+
+impl<T: BufferElement> fmt::Debug for Buffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Buffer")
+            .field("handle", &self.buf)
+            .field("byte_size", &(self.size * mem::size_of::<T>()))
+            .field("len", &self.size)
+            .finish()
+    }
+}
+
+impl<T: BufferElement> Drop for Buffer<T> {
     fn drop(&mut self) {
         unsafe { oidnReleaseBuffer(self.buf) }
     }
 }
+
+unsafe impl<T: BufferElement> Send for Buffer<T> {}
@@ -1,14 +1,54 @@
 use crate::sys::*;
-use crate::Error;
-use std::sync::Arc;
-use std::{ffi::CStr, os::raw::c_char, ptr};
+use crate::{DeviceType, Error, ExternalMemoryType};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, ThreadId};
+use std::{
+    env,
+    ffi::{CStr, CString},
+    fmt, mem,
+    os::raw::c_char,
+    ptr,
+};
 
 /// An Open Image Denoise device (e.g. a CPU).
 ///
 /// Open Image Denoise supports a device concept, which allows different
 /// components of the application to use the API without interfering with each
 /// other.
-pub struct Device(pub(crate) OIDNDevice, pub(crate) Arc<u8>);
+///
+/// The [Arc] is shared with the device's buffers to identify the device they
+/// were created by, and holds its [DeviceType]. The [Mutex] holds the errors
+/// seen by [Device::peek_error] until they are cleared by
+/// [Device::get_error], keyed by the thread they were reported on as Open
+/// Image Denoise tracks errors per thread, and discarded once their thread
+/// exits. Filters on the device may be sent to other threads, so the errors
+/// can't be kept in a [Cell](std::cell::Cell).
+pub struct Device(
+    pub(crate) OIDNDevice,
+    pub(crate) Arc<u8>,
+    Mutex<HashMap<ThreadId, PeekedError>>,
+);
+
+thread_local! {
+    /// Dropped when the thread exits, so errors it peeked can be discarded.
+    static THREAD_ALIVE: Arc<()> = Arc::new(());
+}
+
+/// An error kept by [Device::peek_error] for the thread it was reported on.
+pub(crate) struct PeekedError {
+    thread: Weak<()>,
+    error: (Error, String),
+}
+
+impl PeekedError {
+    fn new(error: (Error, String)) -> Self {
+        // A thread whose locals are being destroyed is exiting, so its error
+        // is discarded right away
+        let thread = THREAD_ALIVE.try_with(Arc::downgrade).unwrap_or_default();
+        PeekedError { thread, error }
+    }
+}
 
 impl Device {
     /// Create a device using the fastest device available to run denoising
@@ -17,7 +57,79 @@ impl Device {
         unsafe {
             oidnCommitDevice(handle);
         }
-        Self(handle, Arc::new(0))
+        Self::committed(handle)
+    }
+
+    /// Create a device like [Device::new], but return the error if the
+    /// device could not be created or failed to initialize, e.g. due to
+    /// unsupported hardware.
+    pub fn new_checked() -> Result<Self, (Error, String)> {
+        let handle = unsafe { oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_DEFAULT) };
+        if handle.is_null() {
+            // Errors without a device are reported through the null device
+            device_error(handle)?;
+            return Err((Error::Unknown, "failed to create the device".to_string()));
+        }
+        unsafe {
+            oidnCommitDevice(handle);
+        }
+        let device = Self::committed(handle);
+        device.get_error()?;
+        Ok(device)
+    }
+
+    /// Create the first device of the `preferred` types which commits without
+    /// an error, trying them in order and then the CPU device, e.g. to use a
+    /// GPU when one works and fall back to the CPU otherwise.
+    ///
+    /// A device counts as working if committing it reports no error and it
+    /// is of the requested type ([DeviceType::Default] accepts any type).
+    /// If even the CPU device reports an error it is still returned, so the
+    /// error can be read with [Device::get_error].
+    pub fn new_with_fallback(preferred: &[DeviceType]) -> Self {
+        for &device_type in preferred {
+            let Some(builder) = DeviceBuilder::new(device_type) else {
+                log_debug!("{device_type:?} device is not supported, trying the next type");
+                continue;
+            };
+            let device = builder.commit();
+            if let Err((_err, _msg)) = device.get_error() {
+                log_debug!("{device_type:?} device failed with {_err:?}: {_msg}");
+                continue;
+            }
+            if device_type == DeviceType::Default || device.device_type() == device_type {
+                return device;
+            }
+        }
+        Self::cpu()
+    }
+
+    /// Create a device of the type named by the `OIDN_DEVICE` environment
+    /// variable (`"cpu"`, `"cuda"`, `"sycl"`, `"hip"`, `"metal"` or
+    /// `"default"`), useful to try different backends without code changes.
+    ///
+    /// Falls back to [Device::new] if the variable is not set, or if the type
+    /// is unknown or not available, which is logged as a warning when the
+    /// `log` feature is enabled.
+    pub fn from_env() -> Self {
+        let Ok(name) = env::var("OIDN_DEVICE") else {
+            return Self::new();
+        };
+        let device_type = match name.parse::<DeviceType>() {
+            Ok(device_type) => device_type,
+            Err(_err) => {
+                log_warn!("OIDN_DEVICE: {_err}, falling back to the default device");
+                return Self::new();
+            }
+        };
+        if let Some(builder) = DeviceBuilder::new(device_type) {
+            return builder.commit();
+        }
+        log_warn!(
+            "OIDN_DEVICE={name} is not available on this system, \
+             falling back to the default device"
+        );
+        Self::new()
     }
 
     /// Create a device to run denoising on the CPU
@@ -26,59 +138,146 @@ impl Device {
         unsafe {
             oidnCommitDevice(handle);
         }
-        Self(handle, Arc::new(0))
+        Self::committed(handle)
     }
 
+    /// Create a CPU device using a fixed number of threads and no thread
+    /// affinity, for reproducible output across runs and machines, e.g. for
+    /// golden image tests. Returns [None] if the CPU device is not available.
+    ///
+    /// Open Image Denoise has no determinism option, so this avoids the
+    /// sources of variation the application controls: GPU backends, whose
+    /// results may differ between runs, drivers and hardware, and a thread
+    /// count that depends on the machine. The tradeoff is speed, denoising on
+    /// the CPU with few threads is much slower than on a GPU. Results may
+    /// still differ between OIDN versions and CPU instruction sets. Use
+    /// [RayTracing::deterministic](crate::RayTracing::deterministic) to check
+    /// filters are only run on such a device.
+    pub fn deterministic(num_threads: u32) -> Option<Self> {
+        let device = DeviceBuilder::new(DeviceType::Cpu)?
+            .num_threads(num_threads)
+            .set_affinity(false)
+            .commit();
+        device.require_type_or_log(DeviceType::Cpu)
+    }
+
+    /// Create a device to run denoising on an NVIDIA GPU using CUDA, returns [None] if
+    /// it is not supported or Open Image Denoise created a device of another
+    /// type instead.
     pub fn cuda() -> Option<Self> {
-        let handle = unsafe { oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_CUDA) };
+        Self::new_of_type(DeviceType::Cuda)
+    }
+
+    /// Create a CUDA device on the GPU `device_id` which queues its work on
+    /// an existing CUDA `stream`, returns [None] if CUDA is not supported.
+    ///
+    /// SYCL queues can only be shared through the C++ API of Open Image
+    /// Denoise, so there is no equivalent for [Device::sycl].
+    ///
+    /// # Safety
+    /// `stream` must be a valid CUDA stream on `device_id` (or null for the
+    /// default stream) and outlive the device.
+    pub unsafe fn cuda_with_stream(device_id: i32, stream: cudaStream_t) -> Option<Self> {
+        let handle = oidnNewCUDADevice(&device_id, &stream, 1);
         if handle.is_null() {
             return None;
         }
-        unsafe {
-            oidnCommitDevice(handle);
-        }
-        Some(Self(handle, Arc::new(0)))
+        oidnCommitDevice(handle);
+        Self::committed(handle).require_type_or_log(DeviceType::Cuda)
     }
 
+    /// Create a device to run denoising on an Intel GPU using SYCL, returns [None] if
+    /// it is not supported or Open Image Denoise created a device of another
+    /// type instead.
     pub fn sycl() -> Option<Self> {
-        let handle = unsafe { oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_SYCL) };
-        if handle.is_null() {
-            return None;
-        }
-        unsafe {
-            oidnCommitDevice(handle);
-        }
-        Some(Self(handle, Arc::new(0)))
+        Self::new_of_type(DeviceType::Sycl)
     }
 
+    /// Create a device to run denoising on an AMD GPU using HIP, returns [None] if
+    /// it is not supported or Open Image Denoise created a device of another
+    /// type instead.
     pub fn hip() -> Option<Self> {
-        let handle = unsafe { oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_HIP) };
-        if handle.is_null() {
-            return None;
+        Self::new_of_type(DeviceType::Hip)
+    }
+
+    /// Create a device to run denoising on an Apple GPU using Metal, returns [None] if
+    /// it is not supported or Open Image Denoise created a device of another
+    /// type instead.
+    pub fn metal() -> Option<Self> {
+        Self::new_of_type(DeviceType::Metal)
+    }
+
+    /// Wraps the handle of a device which was just committed.
+    fn committed(handle: OIDNDevice) -> Self {
+        let device = Self::tagged(handle);
+        if !handle.is_null() {
+            log_debug!("committed {:?} device {handle:?}", device.device_type());
         }
-        unsafe {
-            oidnCommitDevice(handle);
+        device
+    }
+
+    /// Wraps a committed device handle, recording its type for its buffers.
+    fn tagged(handle: OIDNDevice) -> Self {
+        let mut device = Self(handle, Arc::new(0), Mutex::default());
+        if !handle.is_null() {
+            device.1 = Arc::new(device.device_type() as u8);
         }
-        Some(Self(handle, Arc::new(0)))
+        device
     }
 
-    pub fn metal() -> Option<Self> {
-        let handle = unsafe { oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_METAL) };
+    /// Creates and commits a device of `device_type`, returns [None] if the
+    /// type is not supported or the committed device is of another type.
+    fn new_of_type(device_type: DeviceType) -> Option<Self> {
+        let handle = unsafe { oidnNewDevice(device_type.as_raw_oidn_device_type()) };
         if handle.is_null() {
             return None;
         }
         unsafe {
             oidnCommitDevice(handle);
         }
-        Some(Self(handle, Arc::new(0)))
+        Self::committed(handle).require_type_or_log(device_type)
+    }
+
+    /// Returns the device if it is of `device_type`, or releases it and
+    /// returns an [Error::UnsupportedHardware] naming the requested and
+    /// actual type otherwise. GPU devices are requested explicitly to run on
+    /// the GPU, so a device which ended up on another backend (e.g. the CPU
+    /// because of an outdated driver) is released rather than silently
+    /// running much slower than expected.
+    pub fn require_type(self, device_type: DeviceType) -> Result<Self, (Error, String)> {
+        let actual = self.device_type();
+        if actual != device_type {
+            return Err((
+                Error::UnsupportedHardware,
+                format!("requested a {device_type:?} device, but got a {actual:?} device"),
+            ));
+        }
+        Ok(self)
+    }
+
+    /// [Device::require_type] for the constructors returning [None], which
+    /// logs why the device was released.
+    fn require_type_or_log(self, device_type: DeviceType) -> Option<Self> {
+        self.require_type(device_type)
+            .inspect_err(|(_err, _msg)| {
+                log_warn!("{_msg}");
+            })
+            .ok()
     }
 
+    /// Wraps an existing device handle. The device is retained, so the
+    /// returned [Device] holds its own reference and releases only that one
+    /// when dropped: the caller still owns its reference and must release it
+    /// with [oidnReleaseDevice] once it is done with the handle.
+    ///
     /// # Safety
     /// Raw device must not be invalid (e.g. destroyed, null, ect.)
     ///
     /// Raw device must be Committed using [oidnCommitDevice]
     pub unsafe fn from_raw(device: OIDNDevice) -> Self {
-        Self(device, Arc::new(0))
+        oidnRetainDevice(device);
+        log_debug!("wrapped existing device {device:?}");
+        Self::tagged(device)
     }
 
     /// # Safety
@@ -87,26 +286,241 @@ impl Device {
         self.0
     }
 
+    /// Checks if a device of the given type can be created, without creating
+    /// one. [DeviceType::Default] is supported if any device is available.
+    pub fn is_type_supported(device_type: DeviceType) -> bool {
+        let raw_type = device_type.as_raw_oidn_device_type() as i32;
+        let num_devices = unsafe { oidnGetNumPhysicalDevices() };
+        (0..num_devices).any(|id| {
+            device_type == DeviceType::Default
+                || unsafe { oidnGetPhysicalDeviceInt(id, b"type\0" as *const _ as _) } == raw_type
+        })
+    }
+
+    /// Returns the type of device [Device::new] would create, without creating
+    /// one, e.g. to show "Auto (CUDA)" in a UI. Returns [DeviceType::Default]
+    /// if it can't be determined, e.g. because no device is available.
+    ///
+    /// Open Image Denoise lists the physical devices in order of preference,
+    /// so the default is the first one, unless the `OIDN_DEFAULT_DEVICE`
+    /// environment variable selects a device type or physical device ID.
+    pub fn default_backend() -> DeviceType {
+        let num_devices = unsafe { oidnGetNumPhysicalDevices() };
+        let physical_type = |id| {
+            let raw_type = unsafe { oidnGetPhysicalDeviceInt(id, b"type\0" as *const _ as _) };
+            DeviceType::try_from(raw_type as u32).unwrap_or(DeviceType::Default)
+        };
+        if let Ok(value) = env::var("OIDN_DEFAULT_DEVICE") {
+            match (value.parse::<DeviceType>(), value.trim().parse::<i32>()) {
+                (Ok(device_type), _) if device_type != DeviceType::Default => return device_type,
+                (_, Ok(id)) if (0..num_devices).contains(&id) => return physical_type(id),
+                _ => {}
+            }
+        }
+        if num_devices == 0 {
+            return DeviceType::Default;
+        }
+        physical_type(0)
+    }
+
+    /// Returns true if the crate was built with the `stub` feature, in which
+    /// case no denoising is performed and filters copy their input to the
+    /// output unchanged.
+    pub fn is_stub(&self) -> bool {
+        cfg!(feature = "stub")
+    }
+
+    /// Waits for all asynchronous operations running on the device to
+    /// complete.
+    pub fn sync(&self) {
+        unsafe {
+            oidnSyncDevice(self.0);
+        }
+    }
+
+    /// Queries a boolean parameter of the device, e.g. `"managedMemorySupported"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_bool(&self, name: &str) -> bool {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnGetDeviceBool(self.0, name.as_ptr()) }
+    }
+
+    /// Queries an integer parameter of the device, e.g. `"version"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_int(&self, name: &str) -> i32 {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnGetDeviceInt(self.0, name.as_ptr()) }
+    }
+
+    /// Returns the version of the Open Image Denoise library the device was
+    /// created by, encoded as `10000 * major + 100 * minor + patch`, e.g.
+    /// `20301` for 2.3.1.
+    pub fn version(&self) -> i32 {
+        self.get_int("version")
+    }
+
+    /// Returns whether buffers can be created from external memory of
+    /// `memory_type` on this device, e.g. with [Device::create_buffer_from_fd]
+    /// to denoise memory allocated by Vulkan without copying it.
+    pub fn supports_external_memory(&self, memory_type: ExternalMemoryType) -> bool {
+        let types = self.get_int("externalMemoryTypes") as OIDNExternalMemoryTypeFlag;
+        types & memory_type.as_raw_oidn_external_memory_type_flag() != 0
+    }
+
+    /// Returns the number of threads requested for a CPU device with
+    /// [DeviceBuilder::num_threads], or [None] for other device types or if
+    /// the number was left to OIDN (which then uses all available threads).
+    ///
+    /// Open Image Denoise reports the `numThreads` parameter as it was set,
+    /// not the number of threads it actually uses once committed.
+    pub fn num_threads(&self) -> Option<i32> {
+        if !self.is_cpu() {
+            return None;
+        }
+        let threads = self.get_int("numThreads");
+        (threads > 0).then_some(threads)
+    }
+
+    /// Returns the type of the device, e.g. the type picked by [Device::new].
+    /// Types unknown to this crate are reported as [DeviceType::Default].
+    pub fn device_type(&self) -> DeviceType {
+        DeviceType::try_from(self.get_int("type") as u32).unwrap_or_default()
+    }
+
+    /// Returns true if the device runs on the CPU, and so can access host
+    /// memory directly.
+    pub(crate) fn is_cpu(&self) -> bool {
+        self.device_type() == DeviceType::Cpu
+    }
+
+    /// Returns and clears the first error reported by the device since the
+    /// last call.
+    ///
+    /// Reading the error clears it: Open Image Denoise only reports an error
+    /// once, so a later call (e.g. by a central error handler) won't see it.
+    /// Use [Device::peek_error] to check for an error without clearing it.
+    ///
+    /// Open Image Denoise tracks errors per thread, so this only returns
+    /// errors caused by calls made from the current thread. Use a
+    /// [SyncDevice] to share a device between threads.
     pub fn get_error(&self) -> Result<(), (Error, String)> {
-        let mut err_msg = ptr::null();
-        let err = unsafe { oidnGetDeviceError(self.0, &mut err_msg as *mut *const c_char) };
+        match self.take_peeked() {
+            Some(err) => Err(err),
+            None => device_error(self.0),
+        }
+    }
+
+    /// Returns the first error reported by the device like
+    /// [Device::get_error], but without clearing it, so a later
+    /// [Device::get_error] call still returns it.
+    ///
+    /// Open Image Denoise clears its error state once it is read, so the
+    /// error is kept by the [Device] until [Device::get_error],
+    /// [Device::get_error_code] or [Device::drain_errors] returns it on the
+    /// same thread. Like them, it only sees errors of the current thread. The
+    /// error is discarded if the thread exits without reading it.
+    pub fn peek_error(&self) -> Result<(), (Error, String)> {
+        let mut peeked = self.peeked();
+        let err = match peeked.get(&thread::current().id()) {
+            Some(peeked) => peeked.error.clone(),
+            None => match device_error(self.0) {
+                Ok(()) => return Ok(()),
+                Err(err) => peeked
+                    .entry(thread::current().id())
+                    .or_insert(PeekedError::new(err))
+                    .error
+                    .clone(),
+            },
+        };
+        Err(err)
+    }
+
+    /// The errors kept by [Device::peek_error], without those of threads
+    /// which have exited.
+    pub(crate) fn peeked(&self) -> MutexGuard<'_, HashMap<ThreadId, PeekedError>> {
+        // The map is never left in an invalid state, so ignore poisoning
+        let mut peeked = self.2.lock().unwrap_or_else(|e| e.into_inner());
+        peeked.retain(|_, peeked| peeked.thread.strong_count() > 0);
+        peeked
+    }
+
+    /// Removes the error kept by [Device::peek_error] for the current thread.
+    fn take_peeked(&self) -> Option<(Error, String)> {
+        self.peeked()
+            .remove(&thread::current().id())
+            .map(|peeked| peeked.error)
+    }
+
+    /// Returns and clears the first error reported by the device like
+    /// [Device::get_error], but only returns its code, without copying the
+    /// message into a [String]. Useful for checking errors in hot loops.
+    pub fn get_error_code(&self) -> Result<(), Error> {
+        if let Some((err, _)) = self.take_peeked() {
+            return Err(err);
+        }
+        let err = unsafe { oidnGetDeviceError(self.0, ptr::null_mut()) };
         if OIDNError_OIDN_ERROR_NONE == err {
             Ok(())
         } else {
-            let msg = unsafe { CStr::from_ptr(err_msg).to_string_lossy().to_string() };
-            Err(((err as u32).try_into().unwrap(), msg))
+            Err(Error::from_raw(err))
         }
     }
+
+    /// Returns and clears all errors reported by the device since the last
+    /// call, oldest first.
+    ///
+    /// Open Image Denoise currently only keeps the first error until it is
+    /// queried, so this returns at most one error with the current library
+    /// versions, but it won't miss any should that change.
+    pub fn drain_errors(&self) -> Vec<(Error, String)> {
+        std::iter::from_fn(|| self.get_error().err()).collect()
+    }
+}
+
+/// Returns and clears the error of `handle`, or of the current thread for
+/// calls made without a device if `handle` is null.
+pub(crate) fn device_error(handle: OIDNDevice) -> Result<(), (Error, String)> {
+    let mut err_msg: *const c_char = ptr::null();
+    let err = unsafe { oidnGetDeviceError(handle, &mut err_msg as *mut *const c_char) };
+    if OIDNError_OIDN_ERROR_NONE == err {
+        Ok(())
+    } else {
+        let msg = if err_msg.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(err_msg).to_string_lossy().to_string() }
+        };
+        // Codes from newer OIDN versions are reported as Error::Unknown
+        Err((Error::from_raw(err), msg))
+    }
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
+        log_trace!("releasing device {:?}", self.0);
         unsafe {
             oidnReleaseDevice(self.0);
         }
     }
 }
 
+impl fmt::Debug for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Device")
+            .field("handle", &self.0)
+            .field("type", &self.device_type())
+            .finish()
+    }
+}
+
 impl Default for Device {
     fn default() -> Self {
         Self::new()
@@ -114,3 +528,113 @@ impl Default for Device {
 }
 
 unsafe impl Send for Device {}
+
+/// A [Device] which can be shared between threads, e.g. through an [Arc].
+///
+/// Access to the device is serialized, so filters created, executed and
+/// checked for errors within one [SyncDevice::with] call don't interleave
+/// with other threads using the device.
+pub struct SyncDevice {
+    device: Device,
+    lock: Mutex<()>,
+}
+
+impl SyncDevice {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the device.
+    pub fn with<R>(&self, f: impl FnOnce(&Device) -> R) -> R {
+        // A panic in another thread's closure doesn't leave the device in an
+        // invalid state, so ignore poisoning
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        f(&self.device)
+    }
+
+    pub fn into_inner(self) -> Device {
+        self.device
+    }
+}
+
+impl fmt::Debug for SyncDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SyncDevice").field(&self.device.0).finish()
+    }
+}
+
+unsafe impl Sync for SyncDevice {}
+
+/// Configures a device before it is committed.
+///
+/// Some device parameters, such as the verbosity level, are only read by Open
+/// Image Denoise when the device is committed, and so must be set through the
+/// builder before calling [DeviceBuilder::commit]. Committing consumes the
+/// builder and returns a [Device], which has no setters, so these parameters
+/// can't be changed after the fact where OIDN would silently ignore them.
+pub struct DeviceBuilder(OIDNDevice);
+
+impl DeviceBuilder {
+    /// Creates a new, uncommitted device of the given type, returns [None] if
+    /// the device type is not supported.
+    pub fn new(device_type: DeviceType) -> Option<Self> {
+        let handle = unsafe { oidnNewDevice(device_type.as_raw_oidn_device_type()) };
+        if handle.is_null() {
+            return None;
+        }
+        Some(Self(handle))
+    }
+
+    /// Sets the verbosity level of Open Image Denoise's internal logging to
+    /// stderr, from 0 (errors only) to 4 (full debug output). Level 1 and
+    /// above print the device and filter setup.
+    pub fn verbose(self, level: u32) -> Self {
+        unsafe {
+            oidnSetDeviceInt(self.0, b"verbose\0" as *const _ as _, level as i32);
+        }
+        self
+    }
+
+    /// Sets the number of threads used by a CPU device, by default all
+    /// available threads are used. Ignored by other device types.
+    pub fn num_threads(self, num_threads: u32) -> Self {
+        unsafe {
+            oidnSetDeviceInt(self.0, b"numThreads\0" as *const _ as _, num_threads as i32);
+        }
+        self
+    }
+
+    /// Sets whether a CPU device pins its threads to hardware threads, which
+    /// can improve performance but may conflict with the application's own
+    /// thread affinities. Ignored by other device types.
+    pub fn set_affinity(self, set_affinity: bool) -> Self {
+        unsafe {
+            oidnSetDeviceBool(self.0, b"setAffinity\0" as *const _ as _, set_affinity);
+        }
+        self
+    }
+
+    /// Commits the device, after which its parameters can no longer be
+    /// changed.
+    pub fn commit(self) -> Device {
+        let handle = self.0;
+        mem::forget(self);
+        unsafe {
+            oidnCommitDevice(handle);
+        }
+        Device::committed(handle)
+    }
+}
+
+impl Drop for DeviceBuilder {
+    fn drop(&mut self) {
+        unsafe {
+            oidnReleaseDevice(self.0);
+        }
+    }
+}
+
+unsafe impl Send for DeviceBuilder {}
@@ -1,50 +1,882 @@
-use crate::{buffer::Buffer, device::Device, sys::*, Error, Quality};
-use std::mem;
+use crate::{
+    buffer::{AuxImage, Buffer, BufferElement, BufferView, ImageElement},
+    device::{device_error, Device},
+    sys::*,
+    Error, Format, Quality,
+};
+#[cfg(feature = "image")]
+use image::{DynamicImage, Rgb32FImage};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{CStr, CString},
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    os::raw::c_void,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-/// A generic ray tracing denoising filter for denoising
-/// images produces with Monte Carlo ray tracing methods
-/// such as path tracing.
-pub struct RayTracing<'a> {
+/// A handle used to cancel a running filter from another thread.
+///
+/// Cancellation is cooperative: Open Image Denoise checks the token each time
+/// it reports progress, and stops filtering once it has been canceled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that any filter using this token stops as soon as possible.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the canceled state so the token can be reused.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Statistics about a filter execution.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FilterStats {
+    /// Time spent executing the filter, including waiting for the device to
+    /// finish.
+    pub elapsed: Duration,
+}
+
+/// Parameters of the Open Image Denoise `"RT"` filter, kept in one place so
+/// [RayTracing] and the filters it creates (e.g. to prefilter auxiliary
+/// images) apply them the same way.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct CommonFilterParams {
+    /// Whether the color image is HDR
+    pub(crate) hdr: bool,
+    /// Scale applied to the input values, NaN to have OIDN compute it
+    pub(crate) input_scale: f32,
+    pub(crate) quality: OIDNQuality,
+    pub(crate) max_memory_mb: Option<i32>,
+}
+
+impl Default for CommonFilterParams {
+    fn default() -> Self {
+        CommonFilterParams {
+            hdr: false,
+            input_scale: f32::NAN,
+            quality: Quality::Default.as_raw_oidn_quality(),
+            max_memory_mb: None,
+        }
+    }
+}
+
+impl CommonFilterParams {
+    /// Binds all parameters to `filter`.
+    pub(crate) fn apply(&self, filter: &Filter) {
+        filter.set_bool("hdr", self.hdr);
+        self.apply_input_scale(filter);
+        self.apply_limits(filter);
+    }
+
+    /// Binds the input scale to `filter`, leaving it unset if it isn't
+    /// finite to have OIDN compute it implicitly.
+    fn apply_input_scale(&self, filter: &Filter) {
+        if self.input_scale.is_finite() {
+            filter.set_float("inputScale", self.input_scale);
+        } else if filter.get_float("inputScale").is_finite() {
+            // OIDN parameters can't be unset, so restore the default of NaN
+            // to go back to computing the scale implicitly
+            filter.set_float("inputScale", f32::NAN);
+        }
+    }
+
+    /// Binds the quality and memory limit to `filter`, falling back to what
+    /// the OIDN version of its device supports.
+    pub(crate) fn apply_limits(&self, filter: &Filter) {
+        // The fast quality level was added in OIDN 2.3
+        let quality =
+            if self.quality == OIDNQuality_OIDN_QUALITY_FAST && filter.device.version() < 20300 {
+                OIDNQuality_OIDN_QUALITY_BALANCED
+            } else {
+                self.quality
+            };
+        filter.set_int("quality", quality as i32);
+        // -1 lets OIDN pick the limit based on the device
+        filter.set_int("maxMemoryMB", self.max_memory_mb.unwrap_or(-1));
+    }
+}
+
+/// Where the images are stored in a buffer which interleaves the color image
+/// with the albedo and normal images, e.g. a G-buffer, see
+/// [RayTracing::filter_interleaved]. Offsets are in elements from the start of
+/// each pixel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InterleavedLayout {
+    /// Number of elements per pixel of the buffer.
+    pub pixel_elements: usize,
+    /// Offset of the color, which has the filter's number of channels.
+    pub color: usize,
+    /// Offset of the three channel albedo, if the buffer has one.
+    pub albedo: Option<usize>,
+    /// Offset of the three channel normal, if the buffer has one. Normals
+    /// are only used along with an albedo.
+    pub normal: Option<usize>,
+}
+
+impl InterleavedLayout {
+    /// RGB color, albedo and normal in that order, 9 elements per pixel.
+    pub const COLOR_ALBEDO_NORMAL: InterleavedLayout = InterleavedLayout {
+        pixel_elements: 9,
+        color: 0,
+        albedo: Some(3),
+        normal: Some(6),
+    };
+}
+
+/// Progress monitor passed to Open Image Denoise, `user_ptr` points to the
+/// [AtomicBool] of a [CancelToken]. Returning false cancels the filter.
+unsafe extern "C" fn cancel_progress_monitor(user_ptr: *mut c_void, _n: f64) -> bool {
+    let canceled = &*(user_ptr as *const AtomicBool);
+    !canceled.load(Ordering::Relaxed)
+}
+
+/// State of the progress monitor used by [RayTracing::filter_with_timeout].
+struct Deadline<'t> {
+    deadline: Instant,
+    cancel: Option<&'t AtomicBool>,
+    expired: AtomicBool,
+}
+
+/// Progress monitor passed to Open Image Denoise, `user_ptr` points to a
+/// [Deadline]. Returning false cancels the filter.
+unsafe extern "C" fn deadline_progress_monitor(user_ptr: *mut c_void, _n: f64) -> bool {
+    let deadline = &*(user_ptr as *const Deadline);
+    if deadline
+        .cancel
+        .is_some_and(|canceled| canceled.load(Ordering::Relaxed))
+    {
+        return false;
+    }
+    if Instant::now() >= deadline.deadline {
+        deadline.expired.store(true, Ordering::Relaxed);
+        return false;
+    }
+    true
+}
+
+/// Applies the sRGB transfer function to a linear value in `[0, 1]`.
+fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Logs a warning if `values` fall well outside of `[min, max]`, e.g. because
+/// the normals passed weren't normalized.
+#[cfg(all(feature = "validate", debug_assertions))]
+fn warn_out_of_range<T: ImageElement>(name: &str, values: &[T], min: f32, max: f32) {
+    // Allow for some rounding error in the renderer's output
+    const TOLERANCE: f32 = 0.01;
+    let (lowest, highest) = values
+        .iter()
+        .map(|v| v.to_f32())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lowest < min - TOLERANCE
+        || highest > max + TOLERANCE
+        || values.iter().any(|v| v.to_f32().is_nan())
+    {
+        log::warn!(
+            "{name} values range from {lowest} to {highest}, outside the expected range \
+             [{min}, {max}], which degrades denoising quality"
+        );
+    }
+}
+
+/// Computes an input scale mapping the `percentile`-th percentile of the
+/// luminance of `color` to middle grey, the same key OIDN's implicit scale
+/// uses for the average luminance. Pixels without a positive finite luminance
+/// are ignored, returns [None] if there are none.
+fn percentile_input_scale<T: ImageElement>(
+    color: &[T],
+    channels: usize,
+    percentile: f32,
+) -> Option<f32> {
+    const KEY: f32 = 0.18;
+    let mut luminance: Vec<f32> = color
+        .chunks_exact(channels)
+        .map(|pixel| match pixel {
+            [r, g, b, ..] => 0.2126 * r.to_f32() + 0.7152 * g.to_f32() + 0.0722 * b.to_f32(),
+            _ => pixel[0].to_f32(),
+        })
+        .filter(|l| l.is_finite() && *l > 0.0)
+        .collect();
+    if luminance.is_empty() {
+        return None;
+    }
+    let rank = percentile.clamp(0.0, 100.0) / 100.0 * (luminance.len() - 1) as f32;
+    let (_, value, _) = luminance.select_nth_unstable_by(rank.round() as usize, f32::total_cmp);
+    Some(KEY / *value)
+}
+
+/// A low-level Open Image Denoise filter of any type supported by the
+/// device, e.g. `"RT"` or `"RTLightmap"`.
+///
+/// Parameters and images are passed straight through to OIDN, making it
+/// possible to use filter types or parameters this crate doesn't wrap yet.
+/// Prefer [RayTracing] for denoising ray traced images.
+pub struct Filter<'a> {
     handle: OIDNFilter,
     device: &'a Device,
-    albedo: Option<Buffer>,
-    normal: Option<Buffer>,
+}
+
+impl<'a> Filter<'a> {
+    /// Creates a new filter of the type `name` on the device, returning the
+    /// device's error if OIDN doesn't support the filter type.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn new(device: &'a Device, name: &str) -> Result<Filter<'a>, Error> {
+        let name = CString::new(name).unwrap();
+        let filter = Filter::new_unchecked(device, &name);
+        if filter.handle.is_null() {
+            return Err(device
+                .get_error()
+                .err()
+                .map_or(Error::Unknown, |(err, _)| err));
+        }
+        Ok(filter)
+    }
+
+    /// Creates a new filter of the type `name`, the handle is null if
+    /// creation failed.
+    fn new_unchecked(device: &'a Device, name: &CStr) -> Filter<'a> {
+        unsafe {
+            oidnRetainDevice(device.0);
+        }
+        let handle = unsafe { oidnNewFilter(device.0, name.as_ptr()) };
+        log_debug!(
+            "created {name:?} filter {handle:?} on device {:?}",
+            device.0
+        );
+        Filter { handle, device }
+    }
+
+    /// Returns the device the filter was created on.
+    pub fn device(&self) -> &'a Device {
+        self.device
+    }
+
+    /// Sets a boolean parameter of the filter, e.g. `"hdr"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_bool(&self, name: &str, value: bool) {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnSetFilterBool(self.handle, name.as_ptr(), value) }
+    }
+
+    /// Sets an integer parameter of the filter, e.g. `"quality"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_int(&self, name: &str, value: i32) {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnSetFilterInt(self.handle, name.as_ptr(), value) }
+    }
+
+    /// Sets a float parameter of the filter, e.g. `"inputScale"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_float(&self, name: &str, value: f32) {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnSetFilterFloat(self.handle, name.as_ptr(), value) }
+    }
+
+    /// Queries a boolean parameter of the filter, e.g. `"hdr"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_bool(&self, name: &str) -> bool {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnGetFilterBool(self.handle, name.as_ptr()) }
+    }
+
+    /// Queries an integer parameter of the filter, e.g. `"quality"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_int(&self, name: &str) -> i32 {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnGetFilterInt(self.handle, name.as_ptr()) }
+    }
+
+    /// Queries a float parameter of the filter, e.g. `"inputScale"`.
+    ///
+    /// Unknown parameters are reported through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_float(&self, name: &str) -> f32 {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnGetFilterFloat(self.handle, name.as_ptr()) }
+    }
+
+    /// Binds `buffer` to the image parameter `name`, e.g. `"color"`. Strides
+    /// of 0 mean the image is tightly packed. The filter keeps the buffer
+    /// alive until the image is unset or replaced.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_image<T: BufferElement>(
+        &self,
+        name: &str,
+        buffer: &Buffer<T>,
+        format: Format,
+        width: usize,
+        height: usize,
+        byte_offset: usize,
+        pixel_byte_stride: usize,
+        row_byte_stride: usize,
+    ) {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            oidnSetFilterImage(
+                self.handle,
+                name.as_ptr(),
+                buffer.buf,
+                format.as_raw_oidn_format(),
+                width,
+                height,
+                byte_offset,
+                pixel_byte_stride,
+                row_byte_stride,
+            );
+        }
+    }
+
+    /// Unbinds the image parameter `name`.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn unset_image(&self, name: &str) {
+        let name = CString::new(name).unwrap();
+        unsafe { oidnUnsetFilterImage(self.handle, name.as_ptr()) }
+    }
+
+    /// Commits all previous changes to the filter, must be called before
+    /// [Filter::execute].
+    pub fn commit(&self) {
+        unsafe { oidnCommitFilter(self.handle) }
+    }
+
+    /// Executes the filter, blocking until it finishes on all device types.
+    /// Errors are reported through [Device::get_error].
+    pub fn execute(&self) {
+        unsafe { oidnExecuteFilter(self.handle) }
+    }
+
+    /// # Safety
+    /// Raw filter must not be made invalid (e.g. by destroying it)
+    pub unsafe fn raw(&self) -> OIDNFilter {
+        self.handle
+    }
+}
+
+impl fmt::Debug for Filter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+impl Drop for Filter<'_> {
+    fn drop(&mut self) {
+        log_trace!("releasing filter {:?}", self.handle);
+        unsafe {
+            if !self.handle.is_null() {
+                oidnReleaseFilter(self.handle);
+            }
+            oidnReleaseDevice(self.device.0);
+        }
+    }
+}
+
+unsafe impl Send for Filter<'_> {}
+
+/// The images and settings a filter was committed with by
+/// [RayTracing::commit], used to tell if it must be committed again before
+/// executing.
+#[derive(Clone, Copy, PartialEq)]
+struct CommitState {
+    color: OIDNBuffer,
+    color_offset: usize,
+    output: OIDNBuffer,
+    albedo: Option<OIDNBuffer>,
+    normal: Option<OIDNBuffer>,
+    weights: Option<(*const u8, usize)>,
     hdr: bool,
-    input_scale: f32,
+    input_scale: u32,
+    input_scale_percentile: Option<u32>,
     srgb: bool,
     clean_aux: bool,
     img_dims: (usize, usize, usize),
+    channels: usize,
     filter_quality: OIDNQuality,
+    max_memory_mb: Option<i32>,
+    region: Option<(usize, usize, usize, usize)>,
+}
+
+/// A parameter of the underlying OIDN filter set through
+/// [RayTracing::set_bool], [RayTracing::set_int] or [RayTracing::set_float].
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum RawParam {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+}
+
+/// A generic ray tracing denoising filter for denoising
+/// images produces with Monte Carlo ray tracing methods
+/// such as path tracing.
+///
+/// Each image is denoised on its own. Open Image Denoise (up to 2.3) has no
+/// temporal inputs such as the previous denoised frame or motion vectors, so
+/// flickering between the frames of an animation can't be reduced through
+/// the filter and there is no previous frame to set.
+pub struct RayTracing<'a, T: ImageElement = f32> {
+    filter: Filter<'a>,
+    albedo: Option<Buffer<T>>,
+    normal: Option<Buffer<T>>,
+    params: CommonFilterParams,
+    input_scale_percentile: Option<f32>,
+    srgb: bool,
+    clean_aux: bool,
+    aux_prefiltered: bool,
+    /// Whether the albedo or normal buffer may be shared with the application
+    /// or another filter, so setting a slice must not write into it
+    shared_aux: Cell<bool>,
+    img_dims: (usize, usize, usize),
+    channels: usize,
+    cancel: Option<CancelToken>,
+    weights: Option<Vec<u8>>,
+    /// Data parameters set with [RayTracing::set_data], which OIDN reads
+    /// from the vectors' memory
+    data: HashMap<String, Vec<u8>>,
+    /// Parameters set with [RayTracing::set_bool], [RayTracing::set_int] and
+    /// [RayTracing::set_float], so they can be copied to other filters
+    raw_params: HashMap<String, RawParam>,
+    region: Option<(usize, usize, usize, usize)>,
+    deterministic: bool,
+    /// Buffers sharing the memory of the color and output slices last
+    /// filtered on a CPU device, reused while the same slices are passed
+    shared_slices: Cell<[Option<Buffer<T>>; 2]>,
+    /// Copy of the filter with some settings overridden, e.g. by
+    /// [RayTracing::filter_dynamic], kept so its OIDN filter is reused
+    variant: Cell<Option<Box<RayTracing<'a, T>>>>,
+    committed: Cell<Option<CommitState>>,
+    error: Cell<Option<(Error, String)>>,
 }
 
 impl<'a> RayTracing<'a> {
+    /// Creates a new ray tracing filter for 32-bit float images on the
+    /// device, see [RayTracing::new_typed] for other element types.
+    ///
+    /// If OIDN fails to create the filter the error is reported through
+    /// [Device::get_error] and filtering returns [Error::InvalidOperation];
+    /// use [RayTracing::try_new] to catch this up front.
     pub fn new(device: &'a Device) -> RayTracing<'a> {
-        unsafe {
-            oidnRetainDevice(device.0);
+        RayTracing::new_typed(device)
+    }
+
+    /// Creates a new ray tracing filter for 32-bit float images on the
+    /// device, returning the device's error if OIDN fails to create the
+    /// filter.
+    pub fn try_new(device: &'a Device) -> Result<RayTracing<'a>, Error> {
+        RayTracing::try_new_typed(device)
+    }
+
+    /// Denoises the linear `color` image and writes it to `output` encoded
+    /// with the sRGB transfer function and quantized to 8 bits per channel,
+    /// ready for display or saving as e.g. a PNG.
+    ///
+    /// Values are clamped to `[0, 1]`, so HDR images should be tonemapped
+    /// first. Since the input is linear, [RayTracing::srgb] should be false.
+    pub fn filter_to_srgb(&self, color: &[f32], output: &mut [u8]) -> Result<(), Error> {
+        self.filter_to_u8(color, output, true)
+    }
+
+    /// Denoises the `color` image and writes it to `output` quantized to 8
+    /// bits per channel, e.g. for display in an 8-bit framebuffer. Values are
+    /// clamped to `[0, 1]` and, if `srgb_encode` is true, encoded with the
+    /// sRGB transfer function first (see [RayTracing::filter_to_srgb]).
+    pub fn filter_to_u8(
+        &self,
+        color: &[f32],
+        output: &mut [u8],
+        srgb_encode: bool,
+    ) -> Result<(), Error> {
+        self.check_image_len("output", output.len())?;
+        let mut denoised = vec![0.0; color.len()];
+        self.execute_filter(Some(color), &mut denoised)?;
+        for (out, x) in output.iter_mut().zip(denoised) {
+            let x = x.clamp(0.0, 1.0);
+            let x = if srgb_encode { linear_to_srgb(x) } else { x };
+            *out = (x * 255.0).round() as u8;
+        }
+        Ok(())
+    }
+
+    /// Denoises a 16-bit integer image, e.g. loaded from a TIFF, into
+    /// `output`. Values are normalized to `[0, 1]` for filtering and the
+    /// result is rounded and clamped back to the full `u16` range.
+    ///
+    /// `srgb` tells if the values are encoded with the sRGB transfer function
+    /// and overrides [RayTracing::srgb] for this call. If they differ, a copy
+    /// of the filter with the overridden flag is used (see
+    /// [RayTracing::duplicate_config]), which is kept and reused by later
+    /// calls.
+    pub fn filter_u16(&self, color: &[u16], output: &mut [u16], srgb: bool) -> Result<(), Error> {
+        self.check_image_len("color", color.len())?;
+        self.check_image_len("output", output.len())?;
+        let color: Vec<f32> = color.iter().map(|&x| x as f32 / 65535.0).collect();
+        let mut denoised = vec![0.0; color.len()];
+        if srgb == self.srgb {
+            self.execute_filter(Some(&color), &mut denoised)?;
+        } else {
+            self.with_variant(
+                |filter| {
+                    filter.srgb(srgb);
+                },
+                |filter| filter.filter(&color, &mut denoised),
+            )?;
+        }
+        for (out, x) in output.iter_mut().zip(denoised) {
+            *out = (x.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        }
+        Ok(())
+    }
+
+    /// Denoises an image loaded with the `image` crate, returning the
+    /// denoised image in the same color type and bit depth.
+    ///
+    /// The filter's image dimensions, region and sRGB flag are ignored: the
+    /// whole image is filtered, 8 and 16-bit images are treated as sRGB
+    /// encoded and float images as linear. Alpha channels are passed through
+    /// unchanged. Albedo and normal images must match the image's size. The image is filtered by a copy
+    /// of the filter with these settings (see [RayTracing::duplicate_config]),
+    /// which is kept and reused by later calls.
+    #[cfg(feature = "image")]
+    pub fn filter_dynamic(&self, image: &DynamicImage) -> Result<DynamicImage, Error> {
+        let (width, height) = (image.width(), image.height());
+        let color = image.to_rgb32f();
+        let srgb = !matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let mut output = vec![0.0; color.len()];
+        self.with_variant(
+            |filter| {
+                filter
+                    .srgb(srgb)
+                    .image_dimensions(width as usize, height as usize)
+                    .clear_region();
+            },
+            |filter| filter.filter(&color, &mut output),
+        )?;
+        let denoised = Rgb32FImage::from_raw(width, height, output).unwrap();
+        let mut denoised = DynamicImage::ImageRgb32F(denoised);
+        if image.color().has_alpha() {
+            let mut rgba = denoised.to_rgba32f();
+            for (pixel, original) in rgba.pixels_mut().zip(image.to_rgba32f().pixels()) {
+                pixel[3] = original[3];
+            }
+            denoised = DynamicImage::ImageRgba32F(rgba);
         }
-        let filter = unsafe { oidnNewFilter(device.0, b"RT\0" as *const _ as _) };
+        Ok(match image {
+            DynamicImage::ImageLuma8(_) => denoised.to_luma8().into(),
+            DynamicImage::ImageLumaA8(_) => denoised.to_luma_alpha8().into(),
+            DynamicImage::ImageRgb8(_) => denoised.to_rgb8().into(),
+            DynamicImage::ImageRgba8(_) => denoised.to_rgba8().into(),
+            DynamicImage::ImageLuma16(_) => denoised.to_luma16().into(),
+            DynamicImage::ImageLumaA16(_) => denoised.to_luma_alpha16().into(),
+            DynamicImage::ImageRgb16(_) => denoised.to_rgb16().into(),
+            DynamicImage::ImageRgba16(_) => denoised.to_rgba16().into(),
+            _ => denoised,
+        })
+    }
+}
+
+impl<'a, T: ImageElement> RayTracing<'a, T> {
+    /// Creates a new ray tracing filter for images with elements of type `T`
+    /// on the device, e.g. `half::f16` for 16-bit float images.
+    ///
+    /// Failures are handled as in [RayTracing::new].
+    pub fn new_typed(device: &'a Device) -> RayTracing<'a, T> {
         RayTracing {
-            handle: filter,
-            device,
+            filter: Filter::new_unchecked(device, c"RT"),
             albedo: None,
             normal: None,
-            hdr: false,
-            input_scale: f32::NAN,
+            params: CommonFilterParams::default(),
+            input_scale_percentile: None,
             srgb: false,
             clean_aux: false,
+            aux_prefiltered: false,
+            shared_aux: Cell::new(false),
             img_dims: (0, 0, 0),
-            filter_quality: 0,
+            channels: 3,
+            cancel: None,
+            weights: None,
+            data: HashMap::new(),
+            raw_params: HashMap::new(),
+            region: None,
+            deterministic: false,
+            shared_slices: Cell::default(),
+            variant: Cell::new(None),
+            committed: Cell::new(None),
+            error: Cell::new(None),
+        }
+    }
+
+    /// Creates a new ray tracing filter for images with elements of type `T`
+    /// on the device, returning the device's error if OIDN fails to create
+    /// the filter.
+    pub fn try_new_typed(device: &'a Device) -> Result<RayTracing<'a, T>, Error> {
+        let filter = RayTracing::new_typed(device);
+        if filter.filter.handle.is_null() {
+            return Err(device
+                .get_error()
+                .err()
+                .map_or(Error::Unknown, |(err, _)| err));
+        }
+        Ok(filter)
+    }
+
+    /// Creates a new filter on `device` with the same configuration as this
+    /// one: the hdr, srgb, clean_aux, input scale, quality, memory limit,
+    /// image dimensions, region, custom weights, parameters set with
+    /// [RayTracing::set_bool] and its siblings, and auxiliary images.
+    ///
+    /// Auxiliary buffers are shared with the new filter if `device` is the
+    /// device they were created on, and copied to it otherwise. The cancel
+    /// token is not carried over.
+    pub fn duplicate_config<'b>(&self, device: &'b Device) -> RayTracing<'b, T> {
+        let mut filter = RayTracing::new_typed(device);
+        self.copy_config_to(&mut filter);
+        // Setting a slice must not write into the buffers now shared with
+        // the new filter
+        self.shared_aux.set(true);
+        filter
+    }
+
+    /// Copies the configuration to `filter`, see [RayTracing::duplicate_config].
+    /// Custom weights, data and raw parameters are only set again if they
+    /// differ. The auxiliary buffers are shared with `filter` without
+    /// marking them as shared on this filter.
+    fn copy_config_to<'b>(&self, filter: &mut RayTracing<'b, T>) {
+        let device = filter.filter.device;
+        filter.params = self.params;
+        filter.input_scale_percentile = self.input_scale_percentile;
+        filter.srgb = self.srgb;
+        filter.clean_aux = self.clean_aux;
+        filter.img_dims = self.img_dims;
+        filter.channels = self.channels;
+        filter.region = self.region;
+        filter.deterministic = self.deterministic;
+        filter.albedo = self
+            .albedo
+            .as_ref()
+            .and_then(|albedo| device.share_or_copy_buffer(albedo));
+        filter.shared_aux.set(true);
+        filter.normal = self
+            .normal
+            .as_ref()
+            .and_then(|normal| device.share_or_copy_buffer(normal));
+        if let Some(weights) = self
+            .weights
+            .as_ref()
+            .filter(|w| filter.weights.as_ref() != Some(w))
+        {
+            filter.weights(weights);
+        }
+        for (name, data) in &self.data {
+            if filter.data.get(name) != Some(data) {
+                filter.set_data(name, data);
+            }
+        }
+        for (name, &value) in &self.raw_params {
+            if filter.raw_params.get(name) != Some(&value) {
+                filter.set_raw_param(name, value);
+            }
         }
     }
 
+    /// Runs `run` on a copy of the filter with its settings changed by
+    /// `configure`, e.g. to override the sRGB flag for one call. The copy is
+    /// kept for the next call, so its OIDN filter is only created (and loaded
+    /// with custom weights) once and only set up again if the settings
+    /// change. The copy uses this filter's cancel token.
+    ///
+    /// The copy is only used within this call and takes this filter's
+    /// auxiliary buffers again each time, so they aren't marked as shared on
+    /// this filter: setting a slice may still write into them.
+    fn with_variant<R>(
+        &self,
+        configure: impl FnOnce(&mut RayTracing<'a, T>),
+        run: impl FnOnce(&RayTracing<'a, T>) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let mut variant = self
+            .variant
+            .take()
+            .unwrap_or_else(|| Box::new(RayTracing::new_typed(self.filter.device)));
+        self.copy_config_to(&mut variant);
+        match &self.cancel {
+            Some(token)
+                if variant
+                    .cancel
+                    .as_ref()
+                    .is_some_and(|t| Arc::ptr_eq(&t.0, &token.0)) => {}
+            Some(token) => {
+                variant.set_cancel_token(token.clone());
+            }
+            None => {}
+        }
+        configure(&mut variant);
+        let result = run(&variant).or_else(|err| match variant.get_error() {
+            Err((err, msg)) => self.error(err, msg),
+            Ok(()) => Err(err),
+        });
+        self.variant.set(Some(variant));
+        result
+    }
+
     /// Sets the quality of the output, the default is high.
     ///
     /// Balanced lowers the precision, if possible, however
     /// some devices will not support this and so
     /// the result (and performance) will stay the same as high.
-    /// Balanced is recommended for realtime usages.
-    pub fn filter_quality(&mut self, quality: Quality) -> &mut RayTracing<'a> {
-        self.filter_quality = quality.as_raw_oidn_quality();
+    /// Balanced is recommended for realtime usages, and Fast for the lowest
+    /// latency previews (on OIDN older than 2.3 Fast falls back to Balanced).
+    pub fn filter_quality(&mut self, quality: Quality) -> &mut RayTracing<'a, T> {
+        self.params.quality = quality.as_raw_oidn_quality();
+        self
+    }
+
+    /// Configures the filter for reproducible output, e.g. for golden image
+    /// tests: selects [Quality::High], which doesn't reduce the precision on
+    /// devices where lower qualities do, and makes filtering fail with
+    /// [Error::InvalidOperation] unless the filter's device is a CPU device,
+    /// as created by [Device::deterministic].
+    ///
+    /// Open Image Denoise has no determinism option of its own, see
+    /// [Device::deterministic] for the tradeoffs.
+    pub fn deterministic(&mut self) -> &mut RayTracing<'a, T> {
+        self.deterministic = true;
+        self.filter_quality(Quality::High)
+    }
+
+    /// Limits the memory used by the filter to approximately `max_memory_mb`
+    /// megabytes, which makes OIDN denoise large images in more, smaller
+    /// tiles. Pass `None` to restore OIDN's device specific default.
+    ///
+    /// Open Image Denoise doesn't report how much memory a device has
+    /// available, so on e.g. integrated GPUs sharing system memory the budget
+    /// has to be chosen by the application.
+    pub fn max_memory_mb(&mut self, max_memory_mb: Option<i32>) -> &mut RayTracing<'a, T> {
+        self.params.max_memory_mb = max_memory_mb;
+        self
+    }
+
+    /// Commits the filter for the current image dimensions and settings, so
+    /// Open Image Denoise allocates its scratch memory now instead of stalling
+    /// the first filter call. Does nothing if the filter is already committed,
+    /// e.g. by [RayTracing::commit].
+    ///
+    /// OIDN doesn't accept user provided scratch memory, it always allocates
+    /// its own when the filter is committed (`oidnCommitFilter`), bounded by
+    /// [RayTracing::max_memory_mb]. A temporary image is allocated to commit
+    /// the filter with, so the next filter call binds its own images and
+    /// commits again, which reuses the scratch memory as long as the image
+    /// dimensions and settings don't change.
+    pub fn reserve_scratch(&self) -> Result<(), Error> {
+        if self.committed.get().is_some() {
+            return Ok(());
+        }
+        self.check_dimensions()?;
+        let image = self
+            .filter
+            .device
+            .create_uninit_buffer(self.img_dims.2)
+            .ok_or(Error::OutOfMemory)?;
+        self.prepare(None, &image)
+    }
+
+    /// Sets custom trained weights for the filter, replacing the built-in
+    /// ones.
+    ///
+    /// The blob must be the contents of a TZA file produced by Open Image
+    /// Denoise's training toolkit for a network matching this filter's
+    /// configuration (e.g. the same auxiliary inputs). The weights are copied
+    /// and kept alive for the lifetime of the filter.
+    pub fn weights(&mut self, tza: &[u8]) -> &mut RayTracing<'a, T> {
+        let weights = self.weights.insert(tza.to_vec());
+        if self.filter.handle.is_null() {
+            return self;
+        }
+        unsafe {
+            oidnSetSharedFilterData(
+                self.filter.handle,
+                b"weights\0" as *const _ as _,
+                weights.as_mut_ptr() as *mut _,
+                weights.len(),
+            );
+        }
+        self
+    }
+
+    /// Sets a token which can be used to cancel filtering from another thread.
+    ///
+    /// If the token is canceled while the filter runs, filtering returns
+    /// [Error::Canceled].
+    pub fn set_cancel_token(&mut self, token: CancelToken) -> &mut RayTracing<'a, T> {
+        if self.filter.handle.is_null() {
+            self.cancel = Some(token);
+            return self;
+        }
+        unsafe {
+            oidnSetFilterProgressMonitorFunction(
+                self.filter.handle,
+                Some(cancel_progress_monitor),
+                Arc::as_ptr(&token.0) as *mut _,
+            );
+        }
+        self.cancel = Some(token);
         self
     }
 
@@ -55,111 +887,169 @@ impl<'a> RayTracing<'a> {
     /// *world-space* or *view-space* vectors with arbitrary length, values
     /// in `[-1, 1]`.
     ///
+    /// Open Image Denoise only uses the normals to find edges, so neither the
+    /// space nor its handedness matter and no conversion is needed. They
+    /// should however be used consistently, e.g. across the frames of an
+    /// animation, where view-space normals change as the camera moves.
+    /// Both images must have three channels even if the color image has fewer
+    /// (see [RayTracing::image_dimensions_channels]), which is checked when
+    /// filtering.
+    ///
+    /// Slices are copied into buffers owned by the filter while buffers are
+    /// used directly, see [AuxImage].
+    ///
     /// # Panics
     /// - if resource creation fails
-    pub fn albedo_normal(&mut self, albedo: &[f32], normal: &[f32]) -> &mut RayTracing<'a> {
-        match self.albedo.as_mut().and_then(|buf| {
-            if buf.size == albedo.len() {
-                Some(buf)
-            } else {
-                None
-            }
-        }) {
-            None => {
-                self.albedo = Some(self.device.create_buffer(albedo).unwrap());
-            }
-            Some(buf) => {
-                buf.write(albedo)
-                    .expect("we check if the size is the same already");
-            }
-        }
-        match self.normal.as_mut().and_then(|buf| {
-            if buf.size == normal.len() {
-                Some(buf)
-            } else {
-                None
-            }
-        }) {
-            None => {
-                self.albedo = Some(self.device.create_buffer(normal).unwrap());
+    pub fn albedo_normal(
+        &mut self,
+        albedo: impl AuxImage<T>,
+        normal: impl AuxImage<T>,
+    ) -> &mut RayTracing<'a, T> {
+        #[cfg(all(feature = "validate", debug_assertions))]
+        {
+            if let Some(albedo) = albedo.host_slice() {
+                warn_out_of_range("albedo", albedo, 0.0, 1.0);
             }
-            Some(buf) => {
-                buf.write(normal)
-                    .expect("we check if the size is the same already");
+            if let Some(normal) = normal.host_slice() {
+                warn_out_of_range("normal", normal, -1.0, 1.0);
             }
         }
+        let device = self.filter.device;
+        let shared = self.shared_aux.replace(false);
+        let albedo_buffer = self.albedo.take().filter(|_| !shared);
+        self.albedo = Some(albedo.into_buffer(device, albedo_buffer));
+        let normal_buffer = self.normal.take().filter(|_| !shared);
+        self.normal = Some(normal.into_buffer(device, normal_buffer));
+        self.aux_prefiltered = false;
+        self
+    }
+
+    /// Sets albedo and normal buffers which stay bound across filter calls,
+    /// e.g. to denoise a stack of color images of the same geometry without
+    /// uploading the auxiliary images for each of them.
+    ///
+    /// The buffers are shared rather than copied, so the same set can be
+    /// bound to several filters and updated by the application in between
+    /// filter calls. The filter never writes to them: setting a slice with
+    /// [RayTracing::albedo] or [RayTracing::albedo_normal] afterwards copies
+    /// it into a new buffer. The buffers are kept when changing the image
+    /// dimensions as long as the number of pixels stays the same.
+    ///
+    /// Buffers created by another device are copied to the filter's device.
+    ///
+    /// # Panics
+    /// - if resource creation fails
+    pub fn set_shared_aux(
+        &mut self,
+        albedo: &Buffer<T>,
+        normal: &Buffer<T>,
+    ) -> &mut RayTracing<'a, T> {
+        let device = self.filter.device;
+        self.albedo = Some(device.share_or_copy_buffer(albedo).unwrap());
+        self.normal = Some(device.share_or_copy_buffer(normal).unwrap());
+        self.shared_aux.set(true);
+        self.aux_prefiltered = false;
         self
     }
 
     /// Set an input auxiliary image containing the albedo per pixel (three
-    /// channels, values in `[0, 1]`).
+    /// channels, values in `[0, 1]`), from a slice or a buffer (see
+    /// [AuxImage]).
     ///
     /// # Panics
     /// - if resource creation fails
-    pub fn albedo(&mut self, albedo: &[f32]) -> &mut RayTracing<'a> {
-        match self.albedo.as_mut().and_then(|buf| {
-            if buf.size == albedo.len() {
-                Some(buf)
-            } else {
-                None
-            }
-        }) {
-            None => {
-                self.albedo = Some(self.device.create_buffer(albedo).unwrap());
-            }
-            Some(buf) => {
-                buf.write(albedo)
-                    .expect("we check if the size is the same already");
-            }
+    pub fn albedo(&mut self, albedo: impl AuxImage<T>) -> &mut RayTracing<'a, T> {
+        #[cfg(all(feature = "validate", debug_assertions))]
+        if let Some(albedo) = albedo.host_slice() {
+            warn_out_of_range("albedo", albedo, 0.0, 1.0);
         }
+        let shared = self.shared_aux.get();
+        let albedo_buffer = self.albedo.take().filter(|_| !shared);
+        self.albedo = Some(albedo.into_buffer(self.filter.device, albedo_buffer));
+        self.aux_prefiltered = false;
         self
     }
+
     /// Set input auxiliary buffer containing the albedo and normals.
     ///
-    /// Albedo buffer must have three channels per pixel with values in [0, 1].
-    /// Normal must contain the shading normal as three channels per pixel
-    /// *world-space* or *view-space* vectors with arbitrary length, values
-    /// in `[-1, 1]`.
-    ///
-    /// This function is the same as [RayTracing::albedo_normal] but takes buffers instead
-    ///
     /// Returns [None] if either buffer was not created by this device
+    #[deprecated(
+        since = "3.0.0",
+        note = "Please use RayTracing::albedo_normal, which accepts buffers"
+    )]
     pub fn albedo_normal_buffer(
         &mut self,
-        albedo: Buffer,
-        normal: Buffer,
-    ) -> Option<&mut RayTracing<'a>> {
-        if !self.device.same_device_as_buf(&albedo) || !self.device.same_device_as_buf(&normal) {
+        albedo: Buffer<T>,
+        normal: Buffer<T>,
+    ) -> Option<&mut RayTracing<'a, T>> {
+        if !self.filter.device.same_device_as_buf(&albedo)
+            || !self.filter.device.same_device_as_buf(&normal)
+        {
             return None;
         }
-        self.albedo = Some(albedo);
-        self.normal = Some(normal);
-        Some(self)
+        Some(self.albedo_normal(albedo, normal))
     }
 
-    /// Set an input auxiliary buffer containing the albedo per pixel (three
-    /// channels, values in `[0, 1]`).
-    ///
-    /// This function is the same as [RayTracing::albedo] but takes buffers instead
+    /// Set an input auxiliary buffer containing the albedo per pixel.
     ///
     /// Returns [None] if albedo buffer was not created by this device
-    pub fn albedo_buffer(&mut self, albedo: Buffer) -> Option<&mut RayTracing<'a>> {
-        if !self.device.same_device_as_buf(&albedo) {
+    #[deprecated(
+        since = "3.0.0",
+        note = "Please use RayTracing::albedo, which accepts buffers"
+    )]
+    pub fn albedo_buffer(&mut self, albedo: Buffer<T>) -> Option<&mut RayTracing<'a, T>> {
+        if !self.filter.device.same_device_as_buf(&albedo) {
             return None;
         }
-        self.albedo = Some(albedo);
-        Some(self)
+        Some(self.albedo(albedo))
     }
 
-    /// Set whether the color is HDR.
-    pub fn hdr(&mut self, hdr: bool) -> &mut RayTracing<'a> {
-        self.hdr = hdr;
-        self
+    /// Set the albedo and normal images from ranges of buffers, e.g. images
+    /// packed into a single buffer together with the color. The viewed
+    /// elements are copied into buffers owned by the filter.
+    ///
+    /// Returns [None] if either buffer was not created by this device
+    pub fn albedo_normal_view(
+        &mut self,
+        albedo: BufferView<'_, T>,
+        normal: BufferView<'_, T>,
+    ) -> Option<&mut RayTracing<'a, T>> {
+        let albedo = self.copy_view(albedo)?;
+        let normal = self.copy_view(normal)?;
+        Some(self.albedo_normal(albedo, normal))
     }
 
-    #[deprecated(since = "1.3.1", note = "Please use RayTracing::input_scale instead")]
-    pub fn hdr_scale(&mut self, hdr_scale: f32) -> &mut RayTracing<'a> {
-        self.input_scale = hdr_scale;
+    /// Set the albedo image from a range of a buffer, see
+    /// [RayTracing::albedo_normal_view].
+    ///
+    /// Returns [None] if the buffer was not created by this device
+    pub fn albedo_view(&mut self, albedo: BufferView<'_, T>) -> Option<&mut RayTracing<'a, T>> {
+        let albedo = self.copy_view(albedo)?;
+        Some(self.albedo(albedo))
+    }
+
+    /// Copies the elements of `view` into a new buffer on the filter's device.
+    fn copy_view(&self, view: BufferView<'_, T>) -> Option<Buffer<T>> {
+        let device = self.filter.device;
+        if !device.same_device_as_buf(view.buffer()) {
+            return None;
+        }
+        let mut buffer = device.create_uninit_buffer(view.len())?;
+        buffer
+            .copy_from(view.buffer(), 0, view.offset(), view.len())
+            .ok()?;
+        Some(buffer)
+    }
+
+    /// Set whether the color is HDR.
+    pub fn hdr(&mut self, hdr: bool) -> &mut RayTracing<'a, T> {
+        self.params.hdr = hdr;
+        self
+    }
+
+    #[deprecated(since = "1.3.1", note = "Please use RayTracing::input_scale instead")]
+    pub fn hdr_scale(&mut self, hdr_scale: f32) -> &mut RayTracing<'a, T> {
+        self.params.input_scale = hdr_scale;
         self
     }
 
@@ -171,8 +1061,28 @@ impl<'a> RayTracing<'a> {
     /// affects the quality of the output but not the range of the output
     /// values). If not set, the scale is computed implicitly for HDR images
     /// or set to 1 otherwise
-    pub fn input_scale(&mut self, input_scale: f32) -> &mut RayTracing<'a> {
-        self.input_scale = input_scale;
+    pub fn input_scale(&mut self, input_scale: f32) -> &mut RayTracing<'a, T> {
+        self.params.input_scale = input_scale;
+        self.input_scale_percentile = None;
+        self
+    }
+
+    /// Computes the input scale from the color image each time the filter is
+    /// committed, mapping the `percentile`-th percentile (in `[0, 100]`) of
+    /// its luminance to middle grey, instead of using a fixed
+    /// [input scale](RayTracing::input_scale).
+    ///
+    /// OIDN's implicit scale for HDR images averages the luminance, which a
+    /// few very bright pixels (fireflies) can skew enough to wash out the
+    /// result. A percentile ignores them, e.g. `50.0` maps the median
+    /// luminance to middle grey. If the image has no pixels with a positive
+    /// luminance the scale is computed implicitly.
+    ///
+    /// The color image is read back from device memory if needed. Filters
+    /// committed with [RayTracing::commit] keep the scale computed then.
+    pub fn auto_input_scale_percentile(&mut self, percentile: f32) -> &mut RayTracing<'a, T> {
+        self.params.input_scale = f32::NAN;
+        self.input_scale_percentile = Some(percentile);
         self
     }
 
@@ -180,7 +1090,7 @@ impl<'a> RayTracing<'a> {
     /// only) or is linear.
     ///
     /// The output will be encoded with the same curve.
-    pub fn srgb(&mut self, srgb: bool) -> &mut RayTracing<'a> {
+    pub fn srgb(&mut self, srgb: bool) -> &mut RayTracing<'a, T> {
         self.srgb = srgb;
         self
     }
@@ -190,195 +1100,1320 @@ impl<'a> RayTracing<'a> {
     ///
     /// Recommended for highest quality but should not be enabled for noisy
     /// auxiliary images to avoid residual noise.
-    pub fn clean_aux(&mut self, clean_aux: bool) -> &mut RayTracing<'a> {
+    pub fn clean_aux(&mut self, clean_aux: bool) -> &mut RayTracing<'a, T> {
         self.clean_aux = clean_aux;
         self
     }
 
+    /// Denoises the albedo and normal images with separate filters, replaces
+    /// them with the denoised versions and enables
+    /// [clean_aux](RayTracing::clean_aux).
+    ///
+    /// This gives the highest quality for noisy auxiliary images. The
+    /// denoised images are available through [RayTracing::cleaned_albedo] and
+    /// [RayTracing::cleaned_normal] until new auxiliary images are set, e.g.
+    /// to cache them across frames of a static scene.
+    pub fn prefilter_aux(&mut self) -> Result<(), Error> {
+        if let Some(albedo) = &self.albedo {
+            self.albedo = Some(self.prefilter_image("albedo", albedo)?);
+        }
+        if let (Some(_), Some(normal)) = (&self.albedo, &self.normal) {
+            self.normal = Some(self.prefilter_image("normal", normal)?);
+        }
+        self.aux_prefiltered = true;
+        self.clean_aux = true;
+        Ok(())
+    }
+
+    /// Returns the albedo image denoised by [RayTracing::prefilter_aux].
+    pub fn cleaned_albedo(&self) -> Option<&Buffer<T>> {
+        self.albedo.as_ref().filter(|_| self.aux_prefiltered)
+    }
+
+    /// Returns the normal image denoised by [RayTracing::prefilter_aux].
+    pub fn cleaned_normal(&self) -> Option<&Buffer<T>> {
+        self.normal.as_ref().filter(|_| self.aux_prefiltered)
+    }
+
+    /// Denoises the auxiliary image `name` on its own, returning the result
+    /// in a new buffer.
+    fn prefilter_image(&self, name: &str, image: &Buffer<T>) -> Result<Buffer<T>, Error> {
+        self.check_dimensions()?;
+        self.check_aux_len(name, image.size)?;
+        let device = self.filter.device;
+        let output = device
+            .create_uninit_buffer(image.size)
+            .ok_or(Error::OutOfMemory)?;
+        let filter = Filter::new(device, "RT")?;
+        let (width, height, _) = self.img_dims;
+        filter.set_image(name, image, T::format(3), width, height, 0, 0, 0);
+        filter.set_image("output", &output, T::format(3), width, height, 0, 0, 0);
+        self.params.apply_limits(&filter);
+        filter.commit();
+        filter.execute();
+        if let Err((err, msg)) = device.get_error() {
+            return self.error(err, msg);
+        }
+        Ok(output)
+    }
+
     /// sets the dimensions of the denoising image, if new width * new height
     /// does not equal old width * old height
-    pub fn image_dimensions(&mut self, width: usize, height: usize) -> &mut RayTracing<'a> {
-        let buffer_dims = 3 * width * height;
-        match &self.albedo {
-            None => {}
-            Some(buffer) => {
-                if buffer.size != buffer_dims {
-                    self.albedo = None;
-                }
+    pub fn image_dimensions(&mut self, width: usize, height: usize) -> &mut RayTracing<'a, T> {
+        self.image_dimensions_channels(width, height, 3)
+    }
+
+    /// Sets the dimensions of the denoising image along with the number of
+    /// channels per pixel of the color and output images.
+    ///
+    /// The albedo and normal images always have three channels. Open Image
+    /// Denoise 2.2+ supports denoising color images with 1 to 3 channels,
+    /// unsupported channel counts are reported through [Device::get_error].
+    /// Use a single channel to denoise grayscale images such as ambient
+    /// occlusion or shadow passes, the color and output images then hold
+    /// `width * height` floats.
+    ///
+    /// # Panics
+    /// - if `channels` is not in `1..=4`
+    pub fn image_dimensions_channels(
+        &mut self,
+        width: usize,
+        height: usize,
+        channels: usize,
+    ) -> &mut RayTracing<'a, T> {
+        assert!(
+            (1..=4).contains(&channels),
+            "images must have between 1 and 4 channels, got {channels}"
+        );
+        let aux_dims = 3 * width * height;
+        if matches!(&self.albedo, Some(buffer) if buffer.size != aux_dims) {
+            self.albedo = None;
+        }
+        if matches!(&self.normal, Some(buffer) if buffer.size != aux_dims) {
+            self.normal = None;
+        }
+        self.img_dims = (width, height, channels * width * height);
+        self.channels = channels;
+        self
+    }
+
+    /// Restricts filtering to the `width` x `height` region starting at pixel
+    /// `(x, y)` of the full image set with [RayTracing::image_dimensions].
+    ///
+    /// The buffers passed to the filter still contain the full image, only
+    /// the pixels inside the region are read and written. Filtering returns
+    /// [Error::InvalidImageDimensions] if the region does not fit within the
+    /// image.
+    pub fn region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> &mut RayTracing<'a, T> {
+        self.region = Some((x, y, width, height));
+        self
+    }
+
+    /// Removes the region set with [RayTracing::region], so the full image is
+    /// filtered.
+    pub fn clear_region(&mut self) -> &mut RayTracing<'a, T> {
+        self.region = None;
+        self
+    }
+
+    /// Number of elements expected in the albedo and normal buffers.
+    fn aux_len(&self) -> usize {
+        3 * self.img_dims.0 * self.img_dims.1
+    }
+
+    /// Format of the color and output images.
+    fn color_format(&self) -> Format {
+        T::format(self.channels)
+    }
+
+    /// Resets the filter's configuration to its defaults without creating a
+    /// new filter.
+    ///
+    /// This removes the albedo and normal images and restores `hdr`, `srgb`,
+    /// `clean_aux` and `deterministic` to false, the input scale to be computed implicitly and
+    /// the quality to [Quality::Default] and removes the memory limit. The
+    /// image dimensions, cancel token and custom weights are kept.
+    pub fn reset(&mut self) -> &mut RayTracing<'a, T> {
+        self.albedo = None;
+        self.normal = None;
+        self.aux_prefiltered = false;
+        self.shared_aux.set(false);
+        if !self.filter.handle.is_null() {
+            self.filter.unset_image("albedo");
+            self.filter.unset_image("normal");
+        }
+        self.srgb = false;
+        self.clean_aux = false;
+        self.params = CommonFilterParams::default();
+        self.input_scale_percentile = None;
+        self.deterministic = false;
+        self
+    }
+
+    /// Sets a boolean parameter of the underlying OIDN filter which this
+    /// wrapper doesn't expose, e.g. a parameter added by a newer OIDN
+    /// version. The filter is committed again before it next runs.
+    ///
+    /// Parameters this wrapper manages (e.g. `"hdr"` or `"quality"`) are
+    /// overwritten with the wrapper's settings when filtering, so use their
+    /// setters instead. Parameters set this way are copied by
+    /// [RayTracing::duplicate_config], but not cleared by [RayTracing::reset]
+    /// as OIDN parameters can't be unset. Unknown parameters are reported
+    /// through [Device::get_error].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_bool(&mut self, name: &str, value: bool) -> &mut RayTracing<'a, T> {
+        self.set_raw_param(name, RawParam::Bool(value))
+    }
+
+    /// Sets an integer parameter of the underlying OIDN filter which this
+    /// wrapper doesn't expose, see [RayTracing::set_bool].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_int(&mut self, name: &str, value: i32) -> &mut RayTracing<'a, T> {
+        self.set_raw_param(name, RawParam::Int(value))
+    }
+
+    /// Sets a float parameter of the underlying OIDN filter which this
+    /// wrapper doesn't expose, see [RayTracing::set_bool].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_float(&mut self, name: &str, value: f32) -> &mut RayTracing<'a, T> {
+        self.set_raw_param(name, RawParam::Float(value))
+    }
+
+    fn set_raw_param(&mut self, name: &str, value: RawParam) -> &mut RayTracing<'a, T> {
+        if !self.filter.handle.is_null() {
+            match value {
+                RawParam::Bool(value) => self.filter.set_bool(name, value),
+                RawParam::Int(value) => self.filter.set_int(name, value),
+                RawParam::Float(value) => self.filter.set_float(name, value),
             }
         }
-        match &self.normal {
-            None => {}
-            Some(buffer) => {
-                if buffer.size != buffer_dims {
-                    self.normal = None;
-                }
+        self.raw_params.insert(name.to_string(), value);
+        self.committed.set(None);
+        self
+    }
+
+    /// Sets an opaque data parameter of the underlying OIDN filter which this
+    /// wrapper doesn't expose, see [RayTracing::set_bool]. The data is copied
+    /// and kept alive for the lifetime of the filter. Setting `"weights"` is
+    /// the same as calling [RayTracing::weights].
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn set_data(&mut self, name: &str, data: &[u8]) -> &mut RayTracing<'a, T> {
+        if name == "weights" {
+            return self.weights(data);
+        }
+        let mut data = data.to_vec();
+        if !self.filter.handle.is_null() {
+            let c_name = CString::new(name).unwrap();
+            unsafe {
+                oidnSetSharedFilterData(
+                    self.filter.handle,
+                    c_name.as_ptr(),
+                    data.as_mut_ptr() as *mut _,
+                    data.len(),
+                );
             }
         }
-        self.img_dims = (width, height, buffer_dims);
+        // Moving the vector doesn't move its heap allocation shared with OIDN
+        self.data.insert(name.to_string(), data);
+        self.committed.set(None);
         self
     }
 
-    pub fn filter(&self, color: &[f32], output: &mut [f32]) -> Result<(), Error> {
+    /// Queries a boolean parameter of the underlying OIDN filter, e.g.
+    /// `"hdr"`. Parameters reflect the settings of the last filter call, and
+    /// are false if the filter could not be created.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_bool(&self, name: &str) -> bool {
+        !self.filter.handle.is_null() && self.filter.get_bool(name)
+    }
+
+    /// Queries an integer parameter of the underlying OIDN filter, e.g.
+    /// `"quality"`. Parameters reflect the settings of the last filter call,
+    /// and are 0 if the filter could not be created.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_int(&self, name: &str) -> i32 {
+        if self.filter.handle.is_null() {
+            return 0;
+        }
+        self.filter.get_int(name)
+    }
+
+    /// Queries a float parameter of the underlying OIDN filter, e.g.
+    /// `"inputScale"`. Parameters reflect the settings of the last filter
+    /// call, and are 0 if the filter could not be created.
+    ///
+    /// # Panics
+    /// - if `name` contains a nul byte
+    pub fn get_float(&self, name: &str) -> f32 {
+        if self.filter.handle.is_null() {
+            return 0.0;
+        }
+        self.filter.get_float(name)
+    }
+
+    /// Returns the most recent error detected by the wrapper itself (e.g. a
+    /// buffer from another device or mismatched image dimensions) along with a
+    /// message describing it. The error is cleared once it has been read.
+    ///
+    /// Errors raised by Open Image Denoise are reported through
+    /// [Device::get_error] instead.
+    pub fn get_error(&self) -> Result<(), (Error, String)> {
+        match self.error.take() {
+            None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+
+    pub fn filter(&self, color: &[T], output: &mut [T]) -> Result<(), Error> {
+        self.execute_filter(Some(color), output)?;
+        Ok(())
+    }
+
+    /// Same as [RayTracing::filter], but cancels filtering and returns
+    /// [Error::Canceled] once `timeout` has passed, e.g. to bound the time
+    /// spent on a single image in a server. A cancel token set with
+    /// [RayTracing::set_cancel_token] still applies.
+    ///
+    /// The deadline is checked each time Open Image Denoise reports progress,
+    /// so filtering may run somewhat past it depending on how often the
+    /// device does so (e.g. once per tile on the CPU).
+    pub fn filter_with_timeout(
+        &self,
+        color: &[T],
+        output: &mut [T],
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        if self.filter.handle.is_null() {
+            return self.filter(color, output);
+        }
+        let deadline = Deadline {
+            deadline: Instant::now() + timeout,
+            cancel: self.cancel.as_ref().map(|token| token.0.as_ref()),
+            expired: AtomicBool::new(false),
+        };
+        unsafe {
+            oidnSetFilterProgressMonitorFunction(
+                self.filter.handle,
+                Some(deadline_progress_monitor),
+                &deadline as *const Deadline as *mut _,
+            );
+        }
+        let result = self.execute_filter(Some(color), output);
+        // Restore the monitor of the cancel token, if any
+        let (monitor, user_ptr) = match &self.cancel {
+            Some(token) => (
+                Some(cancel_progress_monitor as _),
+                Arc::as_ptr(&token.0) as *mut c_void,
+            ),
+            None => (None, std::ptr::null_mut()),
+        };
+        unsafe {
+            oidnSetFilterProgressMonitorFunction(self.filter.handle, monitor, user_ptr);
+        }
+        if deadline.expired.load(Ordering::Relaxed) {
+            return Err(Error::Canceled);
+        }
+        result?;
+        Ok(())
+    }
+
+    /// Same as [RayTracing::filter], but also returns how long executing the
+    /// filter took.
+    pub fn filter_timed(&self, color: &[T], output: &mut [T]) -> Result<FilterStats, Error> {
         self.execute_filter(Some(color), output)
     }
 
-    pub fn filter_buffer(&self, color: &Buffer, output: &mut Buffer) -> Result<(), Error> {
-        self.execute_filter_buffer(Some(color), output)
+    /// Filters the image in the `color` buffer into the `output` buffer,
+    /// which must both have been created by the filter's device.
+    ///
+    /// Open Image Denoise writes to `output` through its handle, but it's
+    /// still taken by mutable reference: this keeps it from aliasing `color`
+    /// (use [RayTracing::filter_in_place_buffer] to filter in place) and from
+    /// being read or mapped elsewhere while the filter writes to it.
+    pub fn filter_buffer(&self, color: &Buffer<T>, output: &mut Buffer<T>) -> Result<(), Error> {
+        self.execute(color, output)?;
+        Ok(())
+    }
+
+    /// Same as [RayTracing::filter_buffer], but also returns how long
+    /// executing the filter took.
+    pub fn filter_buffer_timed(
+        &self,
+        color: &Buffer<T>,
+        output: &mut Buffer<T>,
+    ) -> Result<FilterStats, Error> {
+        self.execute(color, output)
+    }
+
+    /// Starts filtering `color` into `output` asynchronously and returns right
+    /// away with a [PendingFilter]. `callback` is called from a background
+    /// thread as soon as the device has finished, e.g. to notify an event
+    /// loop. It receives the output buffer, or the error reported while
+    /// running the filter ([Error::Canceled] if the filter's cancel token was
+    /// canceled).
+    ///
+    /// The returned handle borrows the filter, so it can't be reconfigured
+    /// while it runs, and the buffers are moved into the call so they can't
+    /// be dropped or modified in the meantime. The color buffer is released
+    /// after the filter finishes. Dropping the handle waits for the filter
+    /// and the callback to finish.
+    ///
+    /// Validation errors and errors raised by Open Image Denoise while
+    /// queuing the work are returned immediately, and recorded for
+    /// [RayTracing::get_error].
+    pub fn execute_async_with<F>(
+        &mut self,
+        color: Buffer<T>,
+        output: Buffer<T>,
+        callback: F,
+    ) -> Result<PendingFilter<'_, 'a, T>, Error>
+    where
+        T: 'static,
+        F: FnOnce(Result<Buffer<T>, Error>) + Send + 'static,
+    {
+        self.prepare(Some((&color).into()), &output)?;
+        unsafe { oidnExecuteFilterAsync(self.filter.handle) };
+        // Open Image Denoise reports errors per thread, errors raised while
+        // queuing the work are reported on this one
+        if let Err((err, msg)) = self.filter.device.get_error() {
+            return self.error(err, msg);
+        }
+        let retained = RetainedFilter::new(&self.filter);
+        let cancel = self.cancel.clone();
+        let thread = thread::spawn(move || {
+            unsafe { oidnSyncDevice(retained.device) };
+            // Errors raised while running are reported on the syncing thread
+            let result = match device_error(retained.device) {
+                Err((err, _msg)) => {
+                    log_debug!("asynchronous filter failed with {err:?}: {_msg}");
+                    Err(err)
+                }
+                Ok(()) if cancel.as_ref().is_some_and(CancelToken::is_canceled) => {
+                    Err(Error::Canceled)
+                }
+                Ok(()) => Ok(output),
+            };
+            drop((retained, color));
+            callback(result);
+        });
+        Ok(PendingFilter {
+            filter: self,
+            thread: Some(thread),
+        })
+    }
+
+    /// Filters the color image interleaved with albedo and normal images in
+    /// `input` as described by `layout` into `output`, without splitting
+    /// `input` into separate images first. The images are read straight from
+    /// `input` using pixel strides, the filter's own albedo and normal images
+    /// are not used.
+    ///
+    /// `input` must hold `layout.pixel_elements` elements for each pixel of
+    /// the image, `output` holds just the color channels. Both buffers must
+    /// have been created by the filter's device.
+    pub fn filter_interleaved(
+        &self,
+        input: &Buffer<T>,
+        layout: InterleavedLayout,
+        output: &mut Buffer<T>,
+    ) -> Result<(), Error> {
+        self.committed.set(None);
+        // The filter's own auxiliary images aren't used, so only its settings
+        // are checked rather than its whole configuration
+        self.check_settings()?;
+        self.check_buffer_devices(Some(input.into()), output)?;
+        let images = [
+            ("color", Some(layout.color), self.channels),
+            ("albedo", layout.albedo, 3),
+            ("normal", layout.normal, 3),
+        ];
+        for (name, offset, channels) in images {
+            let fits = |offset: usize| {
+                offset
+                    .checked_add(channels)
+                    .is_some_and(|end| end <= layout.pixel_elements)
+            };
+            if offset.is_some_and(|offset| !fits(offset)) {
+                return self.error(
+                    Error::InvalidArgument,
+                    format!(
+                        "{name} with {channels} channels doesn't fit in a {} element pixel",
+                        layout.pixel_elements
+                    ),
+                );
+            }
+        }
+        let (width, height, _) = self.img_dims;
+        let input_len = (width * height).checked_mul(layout.pixel_elements);
+        if input_len != Some(input.size) {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!(
+                    "input has {} elements, expected {width}x{height} pixels of {} elements",
+                    input.size, layout.pixel_elements
+                ),
+            );
+        }
+        self.check_image_len("output", output.size)?;
+        let pixel_stride = layout.pixel_elements * mem::size_of::<T>();
+        self.set_image_strided(
+            "color",
+            input,
+            layout.color,
+            self.color_format(),
+            pixel_stride,
+        );
+        match layout.albedo {
+            Some(albedo) => {
+                self.set_image_strided("albedo", input, albedo, T::format(3), pixel_stride)
+            }
+            None => self.filter.unset_image("albedo"),
+        }
+        let normal = layout.normal.filter(|_| layout.albedo.is_some());
+        match normal {
+            Some(normal) => {
+                self.set_image_strided("normal", input, normal, T::format(3), pixel_stride)
+            }
+            None => self.filter.unset_image("normal"),
+        }
+        self.set_image("output", output, self.color_format());
+        let color = || {
+            let channels = self.channels;
+            input
+                .read()
+                .chunks_exact(layout.pixel_elements)
+                .flat_map(|pixel| pixel[layout.color..layout.color + channels].to_vec())
+                .collect()
+        };
+        self.commit_params(color, layout.albedo.is_some(), normal.is_some());
+        self.run()?;
+        Ok(())
+    }
+
+    /// Binds `color`, `output` and the filter's settings and auxiliary images
+    /// to the filter and commits it, without executing it. Committing sets up
+    /// the denoising network, so this allows e.g. measuring its cost
+    /// separately from [RayTracing::execute].
+    pub fn commit(&self, color: &Buffer<T>, output: &Buffer<T>) -> Result<(), Error> {
+        self.commit_view(color.into(), output)
+    }
+
+    fn commit_view(&self, color: BufferView<'_, T>, output: &Buffer<T>) -> Result<(), Error> {
+        self.prepare(Some(color), output)?;
+        self.committed.set(Some(self.commit_state(color, output)));
+        Ok(())
+    }
+
+    /// Executes the filter on `color` and `output`, returning how long it
+    /// took. The filter is committed first unless it was last committed by
+    /// [RayTracing::commit] with the same buffers and settings.
+    pub fn execute(&self, color: &Buffer<T>, output: &mut Buffer<T>) -> Result<FilterStats, Error> {
+        self.execute_view(color.into(), output)
+    }
+
+    /// Filters the range of a buffer viewed by `color` into `output`, e.g.
+    /// one of several images packed into a single buffer. The view must have
+    /// the length implied by the image dimensions.
+    pub fn filter_view(
+        &self,
+        color: BufferView<'_, T>,
+        output: &mut Buffer<T>,
+    ) -> Result<(), Error> {
+        self.execute_view(color, output)?;
+        Ok(())
+    }
+
+    fn execute_view(
+        &self,
+        color: BufferView<'_, T>,
+        output: &mut Buffer<T>,
+    ) -> Result<FilterStats, Error> {
+        if self.committed.get() != Some(self.commit_state(color, output)) {
+            self.commit_view(color, output)?;
+        }
+        self.run()
+    }
+
+    /// Filters `color` into a newly allocated output buffer sized to the
+    /// image dimensions, and returns it.
+    pub fn denoise_buffer(&self, color: &Buffer<T>) -> Result<Buffer<T>, Error> {
+        let mut output = self
+            .filter
+            .device
+            .create_uninit_buffer(self.img_dims.2)
+            .ok_or(Error::OutOfMemory)?;
+        self.execute_filter_buffer(Some(color.into()), &mut output)?;
+        output.dims = Some((self.img_dims.0, self.img_dims.1, self.channels));
+        Ok(output)
     }
 
-    pub fn filter_in_place(&self, color: &mut [f32]) -> Result<(), Error> {
-        self.execute_filter(None, color)
+    pub fn filter_in_place(&self, color: &mut [T]) -> Result<(), Error> {
+        self.execute_filter(None, color)?;
+        Ok(())
     }
 
-    pub fn filter_in_place_buffer(&self, color: &mut Buffer) -> Result<(), Error> {
-        self.execute_filter_buffer(None, color)
+    pub fn filter_in_place_buffer(&self, color: &mut Buffer<T>) -> Result<(), Error> {
+        self.execute_filter_buffer(None, color)?;
+        Ok(())
     }
 
-    fn execute_filter(&self, color: Option<&[f32]>, output: &mut [f32]) -> Result<(), Error> {
+    fn execute_filter(&self, color: Option<&[T]>, output: &mut [T]) -> Result<FilterStats, Error> {
+        // Validate everything possible before allocating buffers for the
+        // images, so predictable failures don't touch OIDN allocations
+        self.check_config()?;
+        if let Some(color) = color {
+            self.check_image_len("color", color.len())?;
+        }
+        self.check_image_len("output", output.len())?;
+        if self.filter.device.is_cpu() {
+            // CPU devices can filter directly from and into host memory, so
+            // share the slices with OIDN instead of copying them
+            let [cached_color, cached_output] = self.shared_slices.take();
+            let color = color
+                .map(|color| self.share_slice(cached_color, color.as_ptr() as *mut T, color.len()))
+                .transpose()?;
+            let mut out = self.share_slice(cached_output, output.as_mut_ptr(), output.len())?;
+            let result = self.execute_filter_buffer(color.as_ref().map(BufferView::from), &mut out);
+            // The slices may be freed once this returns, so they must not
+            // stay bound to the filter where a later commit could read them
+            self.filter.unset_image("color");
+            self.filter.unset_image("output");
+            self.shared_slices.set([color, Some(out)]);
+            return result;
+        }
         let color = match color {
             None => None,
-            Some(color) => Some(self.device.create_buffer(color).ok_or(Error::OutOfMemory)?),
+            Some(color) => Some(
+                self.filter
+                    .device
+                    .create_typed_buffer(color)
+                    .ok_or(Error::OutOfMemory)?,
+            ),
         };
         let mut out = self
+            .filter
             .device
-            .create_buffer(output)
+            .create_typed_buffer(output)
             .ok_or(Error::OutOfMemory)?;
-        self.execute_filter_buffer(color.as_ref(), &mut out)?;
+        let stats = self.execute_filter_buffer(color.as_ref().map(BufferView::from), &mut out)?;
         unsafe {
             oidnReadBuffer(
                 out.buf,
                 0,
-                out.size * mem::size_of::<f32>(),
+                out.size * mem::size_of::<T>(),
                 output.as_mut_ptr() as *mut _,
             )
         };
+        Ok(stats)
+    }
+
+    /// Returns `cached` if it shares the `len` elements at `data`, or a new
+    /// buffer sharing them otherwise.
+    fn share_slice(
+        &self,
+        cached: Option<Buffer<T>>,
+        data: *mut T,
+        len: usize,
+    ) -> Result<Buffer<T>, Error> {
+        let cached = cached.filter(|buffer| {
+            buffer.size == len && unsafe { oidnGetBufferData(buffer.buf) } as *mut T == data
+        });
+        match cached {
+            Some(buffer) => Ok(buffer),
+            None => unsafe { self.filter.device.create_shared_buffer(data, len) }
+                .ok_or(Error::OutOfMemory),
+        }
+    }
+
+    /// Binds `buffer` as the filter image `name`, restricted to the region
+    /// set with [RayTracing::region] if any.
+    fn set_image(&self, name: &str, buffer: &Buffer<T>, format: Format) {
+        self.set_image_at(name, buffer, 0, format);
+    }
+
+    /// Binds `buffer` starting at element `offset` as the filter image
+    /// `name`, see [RayTracing::set_image].
+    fn set_image_at(&self, name: &str, buffer: &Buffer<T>, offset: usize, format: Format) {
+        // Pass the strides explicitly instead of 0, so images don't depend on
+        // how the OIDN version in use derives the default strides (in-place
+        // filtering has been reported to fail with "row stride smaller than
+        // width * pixel stride" without custom strides)
+        self.set_image_strided(name, buffer, offset, format, format.bytes_per_pixel());
+    }
+
+    /// Binds `buffer` starting at element `offset` as the filter image
+    /// `name`, with pixels `pixel_stride` bytes apart, e.g. to bind one of
+    /// several images interleaved in the same buffer.
+    fn set_image_strided(
+        &self,
+        name: &str,
+        buffer: &Buffer<T>,
+        offset: usize,
+        format: Format,
+        pixel_stride: usize,
+    ) {
+        let row_stride = self.img_dims.0 * pixel_stride;
+        let (width, height, region_offset) = match self.region {
+            None => (self.img_dims.0, self.img_dims.1, 0),
+            Some((x, y, width, height)) => (width, height, y * row_stride + x * pixel_stride),
+        };
+        let byte_offset = offset * mem::size_of::<T>() + region_offset;
+        self.filter.set_image(
+            name,
+            buffer,
+            format,
+            width,
+            height,
+            byte_offset,
+            pixel_stride,
+            row_stride,
+        );
+    }
+
+    /// Records an error and its message so it can be retrieved through
+    /// [RayTracing::get_error].
+    fn error<R>(&self, err: Error, msg: String) -> Result<R, Error> {
+        self.error.set(Some((err, msg)));
+        Err(err)
+    }
+
+    /// Checks that the image is at least one pixel wide and high. Images
+    /// which are a single pixel wide or high are valid.
+    fn check_dimensions(&self) -> Result<(), Error> {
+        let (width, height, _) = self.img_dims;
+        if width == 0 && height == 0 {
+            return self.error(
+                Error::InvalidImageDimensions,
+                "image dimensions were not set, call image_dimensions() before filtering"
+                    .to_string(),
+            );
+        }
+        if width == 0 || height == 0 {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!("image dimensions {width}x{height} are empty"),
+            );
+        }
         Ok(())
     }
 
-    fn execute_filter_buffer(
+    /// Checks that an auxiliary image has the length implied by the image
+    /// dimensions, as albedo and normal images always have three channels.
+    fn check_aux_len(&self, name: &str, len: usize) -> Result<(), Error> {
+        if len != self.aux_len() {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!(
+                    "{name} has {len} elements, expected {} for the {}x{} image with 3 channels",
+                    self.aux_len(),
+                    self.img_dims.0,
+                    self.img_dims.1
+                ),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that an image slice has the length implied by the image
+    /// dimensions and channel count.
+    fn check_image_len(&self, name: &str, len: usize) -> Result<(), Error> {
+        if len != self.img_dims.2 {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!(
+                    "{name} has {len} elements, expected {} for the {}x{} image with {} channels",
+                    self.img_dims.2, self.img_dims.0, self.img_dims.1, self.channels
+                ),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that the color and output buffers were created by the filter's
+    /// device, the auxiliary images are checked by
+    /// [check_config](RayTracing::check_config).
+    fn check_buffer_devices(
         &self,
-        color: Option<&Buffer>,
-        output: &mut Buffer,
+        color: Option<BufferView<'_, T>>,
+        output: &Buffer<T>,
     ) -> Result<(), Error> {
-        if let Some(alb) = &self.albedo {
-            if alb.size != self.img_dims.2 {
-                return Err(Error::InvalidImageDimensions);
+        if let Some(color) = color {
+            self.check_buffer_device("color", color.buffer())?;
+        }
+        self.check_buffer_device("output", output)
+    }
+
+    /// Checks that `buffer` was created by the filter's device.
+    fn check_buffer_device(&self, name: &str, buffer: &Buffer<T>) -> Result<(), Error> {
+        if self.filter.device.same_device_as_buf(buffer) {
+            return Ok(());
+        }
+        let (buffer_type, filter_type) = (buffer.device_type(), self.filter.device.device_type());
+        let msg = if buffer_type == filter_type {
+            format!(
+                "{name} buffer was created on another {buffer_type:?} device than the one the \
+                 filter uses"
+            )
+        } else {
+            format!(
+                "{name} buffer was created on a {buffer_type:?} device but the filter uses a \
+                 {filter_type:?} device"
+            )
+        };
+        self.error(Error::InvalidArgument, msg)
+    }
+
+    /// Checks everything about the filter's configuration which doesn't
+    /// depend on the color and output images: the settings checked by
+    /// [check_settings](RayTracing::check_settings) and the auxiliary images.
+    /// This lets predictable failures return before any buffers are allocated
+    /// for the color and output images.
+    fn check_config(&self) -> Result<(), Error> {
+        self.check_settings()?;
+        if let Some(albedo) = &self.albedo {
+            self.check_buffer_device("albedo", albedo)?;
+            self.check_aux_len("albedo", albedo.size)?;
+            // The normal is only used along with an albedo
+            if let Some(normal) = &self.normal {
+                self.check_buffer_device("normal", normal)?;
+                self.check_aux_len("normal", normal.size)?;
             }
-            unsafe {
-                oidnSetFilterImage(
-                    self.handle,
-                    b"albedo\0" as *const _ as _,
-                    alb.buf,
-                    OIDNFormat_OIDN_FORMAT_FLOAT3,
-                    self.img_dims.0 as _,
-                    self.img_dims.1 as _,
-                    0,
-                    0,
-                    0,
+        }
+        Ok(())
+    }
+
+    /// Checks that the filter was created, can run on its device and that
+    /// its image dimensions and region are valid, i.e. the configuration
+    /// except for the images.
+    fn check_settings(&self) -> Result<(), Error> {
+        if self.filter.handle.is_null() {
+            return self.error(
+                Error::InvalidOperation,
+                "the filter could not be created by the device".into(),
+            );
+        }
+        if self.deterministic && !self.filter.device.is_cpu() {
+            return self.error(
+                Error::InvalidOperation,
+                format!(
+                    "deterministic filtering requires a CPU device, but the filter uses a {:?} \
+                     device",
+                    self.filter.device.device_type()
+                ),
+            );
+        }
+        self.check_dimensions()?;
+        self.check_region()
+    }
+
+    /// Checks the dimensions recorded in the buffers (see
+    /// [Buffer::dimensions]) against the filter's image dimensions. Views of
+    /// part of a buffer aren't checked, as the recorded dimensions describe
+    /// the whole buffer.
+    fn check_buffer_dimensions(
+        &self,
+        color: Option<BufferView<'_, T>>,
+        output: &Buffer<T>,
+    ) -> Result<(), Error> {
+        let (width, height, _) = self.img_dims;
+        let color = color
+            .filter(|color| color.offset() == 0 && color.len() == color.buffer().size)
+            .map(|color| color.buffer());
+        let buffers = [
+            ("color", color, self.channels),
+            ("output", Some(output), self.channels),
+            ("albedo", self.albedo.as_ref(), 3),
+            ("normal", self.normal.as_ref(), 3),
+        ];
+        for (name, buffer, channels) in buffers {
+            let Some(dims) = buffer.and_then(Buffer::dimensions) else {
+                continue;
+            };
+            if dims != (width, height, channels) {
+                return self.error(
+                    Error::InvalidImageDimensions,
+                    format!(
+                        "{name} buffer holds a {}x{} image with {} channels, expected \
+                         {width}x{height} with {channels} channels",
+                        dims.0, dims.1, dims.2
+                    ),
                 );
             }
+        }
+        Ok(())
+    }
 
-            // No use supplying normal if albedo was
-            // not also given.
-            if let Some(norm) = &self.normal {
-                if norm.size != self.img_dims.2 {
-                    return Err(Error::InvalidImageDimensions);
-                }
-                unsafe {
-                    oidnSetFilterImage(
-                        self.handle,
-                        b"normal\0" as *const _ as _,
-                        norm.buf,
-                        OIDNFormat_OIDN_FORMAT_FLOAT3,
-                        self.img_dims.0 as _,
-                        self.img_dims.1 as _,
-                        0,
-                        0,
-                        0,
-                    );
-                }
-            }
+    fn execute_filter_buffer(
+        &self,
+        color: Option<BufferView<'_, T>>,
+        output: &mut Buffer<T>,
+    ) -> Result<FilterStats, Error> {
+        self.prepare(color, output)?;
+        self.run()
+    }
+
+    /// Validates the images, binds them and the parameters to the filter and
+    /// commits it, so it's ready to [run](RayTracing::run).
+    fn prepare(&self, color: Option<BufferView<'_, T>>, output: &Buffer<T>) -> Result<(), Error> {
+        let color = self.bind_images(color, output)?;
+        self.commit_params(|| color.read(), self.albedo.is_some(), self.has_normal());
+        Ok(())
+    }
+
+    /// Validates the images and binds them to the filter, returns the color
+    /// image, which is the output if filtering in place.
+    fn bind_images<'b>(
+        &self,
+        color: Option<BufferView<'b, T>>,
+        output: &'b Buffer<T>,
+    ) -> Result<BufferView<'b, T>, Error> {
+        self.committed.set(None);
+        self.check_config()?;
+        self.check_buffer_devices(color, output)?;
+        self.check_buffer_dimensions(color, output)?;
+        if let Some(alb) = &self.albedo {
+            self.set_image("albedo", alb, T::format(3));
+        } else {
+            // Make sure an albedo bound by a previous run isn't used
+            self.filter.unset_image("albedo");
         }
-        let color_buffer = match color {
-            Some(color) => {
-                if !self.device.same_device_as_buf(color) {
-                    return Err(Error::InvalidArgument);
-                }
-                if color.size != self.img_dims.2 {
-                    return Err(Error::InvalidImageDimensions);
-                }
-                color
-            }
-            None => {
-                if output.size != self.img_dims.2 {
-                    return Err(Error::InvalidImageDimensions);
-                }
-                // actually this is a needed borrow, the compiler complains otherwise
-                #[allow(clippy::needless_borrow)]
-                &output
+
+        // No use supplying normal if albedo was
+        // not also given.
+        match (&self.albedo, &self.normal) {
+            (Some(_), Some(norm)) => {
+                self.set_image("normal", norm, T::format(3));
             }
+            _ => self.filter.unset_image("normal"),
+        }
+        let color = color.unwrap_or_else(|| output.into());
+        self.check_image_len("color", color.len())?;
+        self.check_image_len("output", output.size)?;
+        self.set_image_at("color", color.buffer(), color.offset(), self.color_format());
+        self.set_image("output", output, self.color_format());
+        Ok(color)
+    }
+
+    /// Whether a normal image is used, which requires an albedo image.
+    fn has_normal(&self) -> bool {
+        self.albedo.is_some() && self.normal.is_some()
+    }
+
+    /// Binds the filter's parameters, computing the input scale from the
+    /// `color` image if needed, and commits it. Whether an `albedo` and
+    /// `normal` image are bound is only used for logging.
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn commit_params(&self, color: impl FnOnce() -> Vec<T>, albedo: bool, normal: bool) {
+        let params = match self.input_scale_percentile {
+            Some(percentile) => CommonFilterParams {
+                input_scale: percentile_input_scale(&color(), self.channels, percentile)
+                    .unwrap_or(f32::NAN),
+                ..self.params
+            },
+            None => self.params,
         };
-        unsafe {
-            oidnSetFilterImage(
-                self.handle,
-                b"color\0" as *const _ as _,
-                color_buffer.buf,
-                OIDNFormat_OIDN_FORMAT_FLOAT3,
-                self.img_dims.0 as _,
-                self.img_dims.1 as _,
-                0,
-                0,
-                0,
+        params.apply(&self.filter);
+        self.filter.set_bool("srgb", self.srgb);
+        self.filter.set_bool("cleanAux", self.clean_aux);
+
+        log_debug!(
+            "committing filter {:?}: {}x{} with {} channels, region {:?}, hdr {}, srgb {}, \
+             albedo {}, normal {}, quality {}",
+            self.filter.handle,
+            self.img_dims.0,
+            self.img_dims.1,
+            self.channels,
+            self.region,
+            self.params.hdr,
+            self.srgb,
+            albedo,
+            normal,
+            self.params.quality
+        );
+        self.filter.commit();
+    }
+
+    /// Checks that the region, if any, is not empty and fits in the image.
+    fn check_region(&self) -> Result<(), Error> {
+        let Some((x, y, width, height)) = self.region else {
+            return Ok(());
+        };
+        if width == 0 || height == 0 {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!("region {width}x{height} is empty"),
             );
         }
-        if !self.device.same_device_as_buf(output) {
-            return Err(Error::InvalidArgument);
+        let fits =
+            |start: usize, len: usize, size| start.checked_add(len).is_some_and(|end| end <= size);
+        if !fits(x, width, self.img_dims.0) || !fits(y, height, self.img_dims.1) {
+            return self.error(
+                Error::InvalidImageDimensions,
+                format!(
+                    "region ({x}, {y}) {width}x{height} does not fit in the {}x{} image",
+                    self.img_dims.0, self.img_dims.1
+                ),
+            );
         }
-        if output.size != self.img_dims.2 {
-            return Err(Error::InvalidImageDimensions);
+        Ok(())
+    }
+
+    fn commit_state(&self, color: BufferView<'_, T>, output: &Buffer<T>) -> CommitState {
+        CommitState {
+            color: color.buffer().buf,
+            color_offset: color.offset(),
+            output: output.buf,
+            albedo: self.albedo.as_ref().map(|albedo| albedo.buf),
+            normal: self.normal.as_ref().map(|normal| normal.buf),
+            weights: self
+                .weights
+                .as_ref()
+                .map(|weights| (weights.as_ptr(), weights.len())),
+            hdr: self.params.hdr,
+            input_scale: self.params.input_scale.to_bits(),
+            input_scale_percentile: self.input_scale_percentile.map(f32::to_bits),
+            srgb: self.srgb,
+            clean_aux: self.clean_aux,
+            img_dims: self.img_dims,
+            channels: self.channels,
+            filter_quality: self.params.quality,
+            max_memory_mb: self.params.max_memory_mb,
+            region: self.region,
+        }
+    }
+
+    /// Executes the committed filter.
+    fn run(&self) -> Result<FilterStats, Error> {
+        let start = Instant::now();
+        self.filter.execute();
+        let elapsed = start.elapsed();
+        log_trace!("executed filter {:?} in {elapsed:?}", self.filter.handle);
+        if self.cancel.as_ref().is_some_and(CancelToken::is_canceled) {
+            return Err(Error::Canceled);
         }
+        Ok(FilterStats { elapsed })
+    }
+}
+
+/// References to a filter and its device, held by the thread waiting for an
+/// asynchronous execution so both outlive it even if its [PendingFilter] is
+/// leaked.
+struct RetainedFilter {
+    device: OIDNDevice,
+    filter: OIDNFilter,
+}
+
+impl RetainedFilter {
+    fn new(filter: &Filter) -> Self {
         unsafe {
-            oidnSetFilterImage(
-                self.handle,
-                b"output\0" as *const _ as _,
-                output.buf,
-                OIDNFormat_OIDN_FORMAT_FLOAT3,
-                self.img_dims.0 as _,
-                self.img_dims.1 as _,
-                0,
-                0,
-                0,
-            );
-            oidnSetFilterBool(self.handle, b"hdr\0" as *const _ as _, self.hdr);
-            oidnSetFilterFloat(
-                self.handle,
-                b"inputScale\0" as *const _ as _,
-                self.input_scale,
-            );
-            oidnSetFilterBool(self.handle, b"srgb\0" as *const _ as _, self.srgb);
-            oidnSetFilterBool(self.handle, b"clean_aux\0" as *const _ as _, self.clean_aux);
+            oidnRetainDevice(filter.device.0);
+            oidnRetainFilter(filter.handle);
+        }
+        RetainedFilter {
+            device: filter.device.0,
+            filter: filter.handle,
+        }
+    }
+}
 
-            oidnSetFilterInt(
-                self.handle,
-                b"quality\0" as *const _ as _,
-                self.filter_quality as i32,
-            );
+impl Drop for RetainedFilter {
+    fn drop(&mut self) {
+        unsafe {
+            oidnReleaseFilter(self.filter);
+            oidnReleaseDevice(self.device);
+        }
+    }
+}
+
+unsafe impl Send for RetainedFilter {}
+
+/// A filter executing asynchronously, started by
+/// [RayTracing::execute_async_with].
+///
+/// The filter stays borrowed until it has finished, dropping the handle
+/// waits for the filter and its callback.
+#[must_use = "dropping the handle waits for the filter to finish"]
+pub struct PendingFilter<'f, 'a, T: ImageElement = f32> {
+    filter: &'f mut RayTracing<'a, T>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: ImageElement> PendingFilter<'_, '_, T> {
+    /// Returns whether the filter has finished and its callback returned,
+    /// without blocking.
+    pub fn is_done(&self) -> bool {
+        match &self.thread {
+            Some(thread) => thread.is_finished(),
+            None => true,
+        }
+    }
 
-            oidnCommitFilter(self.handle);
-            oidnExecuteFilter(self.handle);
+    /// Waits for the filter to finish and its callback to return. A panic in
+    /// the callback is propagated.
+    pub fn join(mut self) {
+        self.wait();
+    }
+
+    fn wait(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        if let Err(panic) = thread.join() {
+            if !thread::panicking() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+}
+
+impl<T: ImageElement> Drop for PendingFilter<'_, '_, T> {
+    fn drop(&mut self) {
+        self.wait();
+    }
+}
+
+impl<T: ImageElement> fmt::Debug for PendingFilter<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingFilter")
+            .field("filter", &self.filter.filter.handle)
+            .field("done", &self.is_done())
+            .finish()
+    }
+}
+
+/// Denoises a sequence of frames with the same dimensions and settings, e.g.
+/// the frames of an animation.
+///
+/// The filter is committed and its color and output buffers are allocated
+/// once, so each frame only uploads the color image, executes the filter and
+/// reads back the result. With an
+/// [automatic input scale](RayTracing::auto_input_scale_percentile) the scale
+/// is computed from each frame instead, which commits the filter again.
+pub struct FrameDenoiser<'a, T: ImageElement = f32> {
+    filter: RayTracing<'a, T>,
+    color: Buffer<T>,
+    output: Buffer<T>,
+}
+
+impl<'a, T: ImageElement> FrameDenoiser<'a, T> {
+    /// Creates a frame denoiser from a fully configured filter, the filter's
+    /// settings and auxiliary images are used for every frame.
+    pub fn new(filter: RayTracing<'a, T>) -> Result<FrameDenoiser<'a, T>, Error> {
+        let device = filter.filter.device;
+        let color = device
+            .create_uninit_buffer(filter.img_dims.2)
+            .ok_or(Error::OutOfMemory)?;
+        let output = device
+            .create_uninit_buffer(filter.img_dims.2)
+            .ok_or(Error::OutOfMemory)?;
+        // The color buffer is uninitialized, so an automatic input scale is
+        // only computed once the first frame is uploaded
+        if filter.input_scale_percentile.is_some() {
+            filter.bind_images(Some((&color).into()), &output)?;
+        } else {
+            filter.prepare(Some((&color).into()), &output)?;
+        }
+        Ok(FrameDenoiser {
+            filter,
+            color,
+            output,
+        })
+    }
+
+    /// Denoises `color` into `output`, both must have the length implied by
+    /// the filter's image dimensions.
+    pub fn denoise_into(&mut self, color: &[T], output: &mut [T]) -> Result<(), Error> {
+        self.filter.check_image_len("color", color.len())?;
+        self.filter.check_image_len("output", output.len())?;
+        self.color
+            .write(color)
+            .ok_or(Error::InvalidImageDimensions)?;
+        if self.filter.input_scale_percentile.is_some() {
+            self.filter.commit_params(
+                || color.to_vec(),
+                self.filter.albedo.is_some(),
+                self.filter.has_normal(),
+            );
         }
+        self.filter.run()?;
+        self.output
+            .read_to_slice(output)
+            .ok_or(Error::InvalidImageDimensions)?;
         Ok(())
     }
+
+    /// Returns the error message of the last frame which failed, see
+    /// [RayTracing::get_error].
+    pub fn get_error(&self) -> Result<(), (Error, String)> {
+        self.filter.get_error()
+    }
+
+    /// The filter used to denoise the frames, e.g. to query its parameters.
+    pub fn filter(&self) -> &RayTracing<'a, T> {
+        &self.filter
+    }
+}
+
+/// A pool of ray tracing filters keyed by their image dimensions, so tiled or
+/// multi-resolution workloads can reuse filters instead of creating a new one
+/// for every tile.
+///
+/// [FilterPool::get] hands out a filter with the requested dimensions, which
+/// is returned to the pool when the [PooledFilter] guard is dropped. Returned
+/// filters are [reset](RayTracing::reset) and their region is cleared, their
+/// custom weights and cancel token are kept.
+#[allow(clippy::type_complexity)]
+pub struct FilterPool<'a, T: ImageElement = f32> {
+    device: &'a Device,
+    /// Available filters keyed by their width and height
+    filters: RefCell<HashMap<(usize, usize), Vec<RayTracing<'a, T>>>>,
 }
 
-impl Drop for RayTracing<'_> {
+impl<'a, T: ImageElement> FilterPool<'a, T> {
+    /// Creates an empty pool of filters on `device`.
+    pub fn new(device: &'a Device) -> FilterPool<'a, T> {
+        FilterPool {
+            device,
+            filters: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a filter for `width` x `height` images from the pool, creating a
+    /// new one if none is available.
+    pub fn get(&self, width: usize, height: usize) -> PooledFilter<'_, 'a, T> {
+        let filter = self
+            .filters
+            .borrow_mut()
+            .get_mut(&(width, height))
+            .and_then(Vec::pop);
+        let filter = filter.unwrap_or_else(|| {
+            let mut filter = RayTracing::new_typed(self.device);
+            filter.image_dimensions(width, height);
+            filter
+        });
+        PooledFilter {
+            pool: self,
+            filter: Some(filter),
+        }
+    }
+
+    /// Number of filters currently available in the pool.
+    pub fn len(&self) -> usize {
+        self.filters.borrow().values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases all filters in the pool.
+    pub fn clear(&self) {
+        self.filters.borrow_mut().clear();
+    }
+}
+
+impl<T: ImageElement> fmt::Debug for FilterPool<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterPool")
+            .field("device", self.device)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// A filter borrowed from a [FilterPool], which is returned to the pool when
+/// dropped.
+pub struct PooledFilter<'p, 'a, T: ImageElement = f32> {
+    pool: &'p FilterPool<'a, T>,
+    filter: Option<RayTracing<'a, T>>,
+}
+
+impl<'a, T: ImageElement> Deref for PooledFilter<'_, 'a, T> {
+    type Target = RayTracing<'a, T>;
+
+    fn deref(&self) -> &RayTracing<'a, T> {
+        self.filter.as_ref().unwrap()
+    }
+}
+
+impl<'a, T: ImageElement> DerefMut for PooledFilter<'_, 'a, T> {
+    fn deref_mut(&mut self) -> &mut RayTracing<'a, T> {
+        self.filter.as_mut().unwrap()
+    }
+}
+
+impl<T: ImageElement> Drop for PooledFilter<'_, '_, T> {
     fn drop(&mut self) {
-        unsafe {
-            oidnReleaseFilter(self.handle);
-            oidnReleaseDevice(self.device.0);
+        let Some(mut filter) = self.filter.take() else {
+            return;
+        };
+        filter.reset().clear_region();
+        // The filter may have been resized while borrowed, so key it by its
+        // current dimensions
+        let (width, height, _) = filter.img_dims;
+        if filter.channels != 3 {
+            filter.image_dimensions(width, height);
         }
+        self.pool
+            .filters
+            .borrow_mut()
+            .entry((width, height))
+            .or_default()
+            .push(filter);
+    }
+}
+
+impl<T: ImageElement> fmt::Debug for PooledFilter<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PooledFilter").field(&self.filter).finish()
+    }
+}
+
+impl<T: ImageElement> fmt::Debug for FrameDenoiser<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameDenoiser")
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+impl<T: ImageElement> fmt::Debug for RayTracing<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RayTracing")
+            .field("handle", &self.filter.handle)
+            .field("hdr", &self.params.hdr)
+            .field("input_scale", &self.params.input_scale)
+            .field("input_scale_percentile", &self.input_scale_percentile)
+            .field("srgb", &self.srgb)
+            .field("clean_aux", &self.clean_aux)
+            .field("quality", &self.params.quality)
+            .field("max_memory_mb", &self.params.max_memory_mb)
+            .field("dimensions", &(self.img_dims.0, self.img_dims.1))
+            .field("channels", &self.channels)
+            .field("region", &self.region)
+            .field("deterministic", &self.deterministic)
+            .field("albedo", &self.albedo.is_some())
+            .field("normal", &self.normal.is_some())
+            .finish()
     }
 }
 
-unsafe impl Send for RayTracing<'_> {}
+unsafe impl<T: ImageElement> Send for RayTracing<'_, T> {}
@@ -30,23 +30,99 @@
 //!
 //! // Save out or display filter_output image.
 //! ```
+//!
+//! For one-off use, [`denoise`] runs the whole process on a default device in
+//! a single call.
+//!
+//! ## Features
+//!
+//! - `half`: Allows creating [`Buffer`]s of `half::f16` elements.
+//! - `image`: Adds `RayTracing::filter_dynamic` to denoise
+//!   `image::DynamicImage`s directly.
+//! - `static`: Links the static Open Image Denoise libraries, see the README
+//!   for the requirements.
+//! - `validate`: In debug builds, logs a warning through the `log` crate if
+//!   albedo or normal values passed as slices are outside their documented
+//!   ranges.
+//! - `bindgen`: Generates [`sys`] from the installed Open Image Denoise
+//!   headers at build time instead of using the checked-in bindings, so they
+//!   always match the installed version. Requires libclang.
+//! - `log`: Logs device and filter creation, commits and executions through
+//!   the `log` crate, at the `debug` and `trace` levels.
+//! - `stub`: Builds without linking Open Image Denoise, replacing it with a
+//!   no-op implementation that copies the color image to the output
+//!   unchanged (and can't be canceled). Useful for CI or downstream crates
+//!   that only need to compile and link. Use [`Device::is_stub`] to check for
+//!   it at runtime.
 
 use num_enum::TryFromPrimitive;
+use std::{fmt, str::FromStr};
+
+/// Logs a message at the debug level if the `log` feature is enabled.
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!(target: "oidn", $($arg)*);
+    };
+}
+
+/// Logs a message at the warn level if the `log` feature is enabled.
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::warn!(target: "oidn", $($arg)*);
+    };
+}
+
+/// Logs a message at the trace level if the `log` feature is enabled.
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::trace!(target: "oidn", $($arg)*);
+    };
+}
 
 pub mod buffer;
 pub mod device;
 pub mod filter;
+#[cfg(not(any(feature = "bindgen", feature = "stub")))]
+#[allow(non_upper_case_globals, non_camel_case_types, non_snake_case)]
+pub mod sys;
+#[cfg(all(feature = "bindgen", not(feature = "stub")))]
 #[allow(non_upper_case_globals, non_camel_case_types, non_snake_case)]
+pub mod sys {
+    include!(concat!(env!("OUT_DIR"), "/sys.rs"));
+}
+#[cfg(feature = "stub")]
+#[allow(
+    non_upper_case_globals,
+    non_camel_case_types,
+    non_snake_case,
+    clippy::missing_safety_doc,
+    clippy::too_many_arguments
+)]
+#[path = "stub.rs"]
 pub mod sys;
 #[cfg(test)]
 mod tests;
 
 #[doc(inline)]
-pub use buffer::Buffer;
+pub use buffer::{AuxImage, Buffer, BufferElement, BufferView, HostBuffer, ImageElement};
 #[doc(inline)]
-pub use device::Device;
+pub use device::{Device, DeviceBuilder, SyncDevice};
 #[doc(inline)]
-pub use filter::RayTracing;
+pub use filter::{
+    CancelToken, Filter, FilterPool, FilterStats, FrameDenoiser, InterleavedLayout, PendingFilter,
+    PooledFilter, RayTracing,
+};
+
+/// Re-exports the types needed for typical denoising, so they can be imported
+/// with `use oidn::prelude::*;`.
+pub mod prelude {
+    pub use crate::{
+        Buffer, BufferView, Device, DeviceType, Error, Format, Quality, RayTracing, Storage,
+    };
+}
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive)]
@@ -56,18 +132,121 @@ pub enum Error {
     InvalidArgument = sys::OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
     InvalidOperation = sys::OIDNError_OIDN_ERROR_INVALID_OPERATION,
     OutOfMemory = sys::OIDNError_OIDN_ERROR_OUT_OF_MEMORY,
-    UnsupportedFormat = sys::OIDNError_OIDN_ERROR_UNSUPPORTED_HARDWARE,
+    UnsupportedHardware = sys::OIDNError_OIDN_ERROR_UNSUPPORTED_HARDWARE,
     Canceled = sys::OIDNError_OIDN_ERROR_CANCELLED,
     InvalidImageDimensions,
 }
 
+impl Error {
+    #[deprecated(
+        since = "3.0.0",
+        note = "Please use Error::UnsupportedHardware instead"
+    )]
+    #[allow(non_upper_case_globals)]
+    pub const UnsupportedFormat: Error = Error::UnsupportedHardware;
+
+    /// Converts a raw Open Image Denoise error code, mapping codes unknown to
+    /// this crate to [Error::Unknown].
+    pub fn from_raw(code: sys::OIDNError) -> Error {
+        match Error::try_from(code) {
+            // Not an OIDN error code, only produced by the wrapper
+            Ok(Error::InvalidImageDimensions) | Err(_) => Error::Unknown,
+            Ok(err) => err,
+        }
+    }
+}
+
+impl From<Error> for sys::OIDNError {
+    /// Converts to the raw Open Image Denoise error code. Errors produced by
+    /// the wrapper itself map to the closest OIDN error.
+    fn from(err: Error) -> sys::OIDNError {
+        match err {
+            Error::InvalidImageDimensions => sys::OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
+            err => err as sys::OIDNError,
+        }
+    }
+}
+
+impl PartialEq<sys::OIDNError> for Error {
+    fn eq(&self, code: &sys::OIDNError) -> bool {
+        sys::OIDNError::from(*self) == *code
+    }
+}
+
+/// Options for the one-shot [denoise] function.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DenoiseOptions<'a> {
+    /// Whether the color is HDR, see [RayTracing::hdr].
+    pub hdr: bool,
+    /// Whether the color is sRGB encoded, see [RayTracing::srgb].
+    pub srgb: bool,
+    /// Optional three channel albedo image.
+    pub albedo: Option<&'a [f32]>,
+    /// Optional three channel normal image, requires `albedo` to be set.
+    pub normal: Option<&'a [f32]>,
+}
+
+/// Denoises a three channel `width` x `height` color image on the default
+/// device, returning the denoised image.
+///
+/// This creates a new device and filter on each call, so prefer using
+/// [Device] and [RayTracing] directly when denoising many images.
+///
+/// Returns [Error::InvalidImageDimensions] if the albedo or normal image
+/// doesn't hold three channels for each pixel.
+pub fn denoise(
+    color: &[f32],
+    width: usize,
+    height: usize,
+    opts: DenoiseOptions,
+) -> Result<Vec<f32>, Error> {
+    let aux_len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3));
+    if [opts.albedo, opts.normal]
+        .iter()
+        .flatten()
+        .any(|aux| Some(aux.len()) != aux_len)
+    {
+        return Err(Error::InvalidImageDimensions);
+    }
+    let device = Device::new();
+    let mut filter = RayTracing::new(&device);
+    filter
+        .hdr(opts.hdr)
+        .srgb(opts.srgb)
+        .image_dimensions(width, height);
+    match (opts.albedo, opts.normal) {
+        (Some(albedo), Some(normal)) => {
+            filter.albedo_normal(albedo, normal);
+        }
+        (Some(albedo), None) => {
+            filter.albedo(albedo);
+        }
+        (None, Some(_)) => return Err(Error::InvalidArgument),
+        (None, None) => {}
+    }
+    let mut output = vec![0.0; color.len()];
+    filter.filter(color, &mut output)?;
+    device.get_error().map_err(|(err, _)| err)?;
+    Ok(output)
+}
+
+/// The quality/performance tradeoff of a filter.
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive, Default)]
 pub enum Quality {
+    /// The default quality of the filter, currently [Quality::High].
     #[default]
     Default = sys::OIDNQuality_OIDN_QUALITY_DEFAULT,
+    /// Faster than high quality, recommended for interactive use. Requires
+    /// OIDN 2.0.
     Balanced = sys::OIDNQuality_OIDN_QUALITY_BALANCED,
+    /// Highest quality, recommended for final frame rendering. Requires
+    /// OIDN 2.0.
     High = sys::OIDNQuality_OIDN_QUALITY_HIGH,
+    /// Lowest latency at the cost of quality, intended for real-time previews.
+    /// Requires OIDN 2.3, older versions fall back to [Quality::Balanced].
     Fast = sys::OIDNQuality_OIDN_QUALITY_FAST,
 }
 
@@ -81,3 +260,227 @@ impl Quality {
         }
     }
 }
+
+impl FromStr for Quality {
+    type Err = ParseEnumError;
+
+    /// Parses `"default"`, `"balanced"`, `"high"` or `"fast"`, ignoring case.
+    fn from_str(s: &str) -> Result<Quality, ParseEnumError> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(Quality::Default),
+            "balanced" => Ok(Quality::Balanced),
+            "high" => Ok(Quality::High),
+            "fast" => Ok(Quality::Fast),
+            _ => Err(ParseEnumError {
+                input: s.to_owned(),
+                expected: &["default", "balanced", "high", "fast"],
+            }),
+        }
+    }
+}
+
+impl TryFrom<&str> for Quality {
+    type Error = ParseEnumError;
+
+    fn try_from(s: &str) -> Result<Quality, ParseEnumError> {
+        s.parse()
+    }
+}
+
+/// The type of an Open Image Denoise [Device].
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive, Default)]
+pub enum DeviceType {
+    /// Let Open Image Denoise pick the fastest device available.
+    #[default]
+    Default = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_DEFAULT,
+    Cpu = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_CPU,
+    Sycl = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_SYCL,
+    Cuda = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_CUDA,
+    Hip = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_HIP,
+    Metal = sys::OIDNDeviceType_OIDN_DEVICE_TYPE_METAL,
+}
+
+impl DeviceType {
+    pub fn as_raw_oidn_device_type(&self) -> sys::OIDNDeviceType {
+        *self as sys::OIDNDeviceType
+    }
+}
+
+impl FromStr for DeviceType {
+    type Err = ParseEnumError;
+
+    /// Parses `"default"`, `"cpu"`, `"sycl"`, `"cuda"`, `"hip"` or `"metal"`,
+    /// ignoring case.
+    fn from_str(s: &str) -> Result<DeviceType, ParseEnumError> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(DeviceType::Default),
+            "cpu" => Ok(DeviceType::Cpu),
+            "sycl" => Ok(DeviceType::Sycl),
+            "cuda" => Ok(DeviceType::Cuda),
+            "hip" => Ok(DeviceType::Hip),
+            "metal" => Ok(DeviceType::Metal),
+            _ => Err(ParseEnumError {
+                input: s.to_owned(),
+                expected: &["default", "cpu", "sycl", "cuda", "hip", "metal"],
+            }),
+        }
+    }
+}
+
+impl TryFrom<&str> for DeviceType {
+    type Error = ParseEnumError;
+
+    fn try_from(s: &str) -> Result<DeviceType, ParseEnumError> {
+        s.parse()
+    }
+}
+
+/// Error returned when parsing a [Quality] or [DeviceType] from a string
+/// which doesn't name any of its variants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    input: String,
+    expected: &'static [&'static str],
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown value \"{}\", expected one of: {}",
+            self.input,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
+/// Where the memory of a [Buffer] is stored.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive, Default)]
+pub enum Storage {
+    /// The default storage for the device.
+    #[default]
+    Default = sys::OIDNStorage_OIDN_STORAGE_UNDEFINED,
+    /// Stored on the host, accessible by both host and device.
+    Host = sys::OIDNStorage_OIDN_STORAGE_HOST,
+    /// Stored on the device, not accessible by the host.
+    Device = sys::OIDNStorage_OIDN_STORAGE_DEVICE,
+    /// Automatically migrated between host and device, accessible by both.
+    Managed = sys::OIDNStorage_OIDN_STORAGE_MANAGED,
+}
+
+impl Storage {
+    pub fn as_raw_oidn_storage(&self) -> sys::OIDNStorage {
+        *self as sys::OIDNStorage
+    }
+}
+
+/// A type of external memory which a [Device] may be able to import as a
+/// [Buffer], e.g. memory exported by Vulkan, see
+/// [Device::supports_external_memory].
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive)]
+pub enum ExternalMemoryType {
+    /// An opaque POSIX file descriptor.
+    OpaqueFd = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_OPAQUE_FD,
+    /// A Linux dma-buf file descriptor.
+    DmaBuf = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_DMA_BUF,
+    /// An NT handle.
+    OpaqueWin32 = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_OPAQUE_WIN32,
+    /// A global share (KMT) handle.
+    OpaqueWin32Kmt =
+        sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_OPAQUE_WIN32_KMT,
+    /// An NT handle to a Direct3D 11 texture.
+    D3D11Texture = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D11_TEXTURE,
+    /// A global share (KMT) handle to a Direct3D 11 texture.
+    D3D11TextureKmt =
+        sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D11_TEXTURE_KMT,
+    /// An NT handle to a Direct3D 11 resource.
+    D3D11Resource = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D11_RESOURCE,
+    /// A global share (KMT) handle to a Direct3D 11 resource.
+    D3D11ResourceKmt =
+        sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D11_RESOURCE_KMT,
+    /// An NT handle to a Direct3D 12 heap.
+    D3D12Heap = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D12_HEAP,
+    /// An NT handle to a Direct3D 12 committed resource.
+    D3D12Resource = sys::OIDNExternalMemoryTypeFlag_OIDN_EXTERNAL_MEMORY_TYPE_FLAG_D3D12_RESOURCE,
+}
+
+impl ExternalMemoryType {
+    pub fn as_raw_oidn_external_memory_type_flag(&self) -> sys::OIDNExternalMemoryTypeFlag {
+        *self as sys::OIDNExternalMemoryTypeFlag
+    }
+}
+
+/// The pixel format of a filter image.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, TryFromPrimitive)]
+pub enum Format {
+    /// One 32-bit float channel.
+    Float = sys::OIDNFormat_OIDN_FORMAT_FLOAT,
+    /// Two 32-bit float channels.
+    Float2 = sys::OIDNFormat_OIDN_FORMAT_FLOAT2,
+    /// Three 32-bit float channels, e.g. RGB color.
+    Float3 = sys::OIDNFormat_OIDN_FORMAT_FLOAT3,
+    /// Four 32-bit float channels.
+    Float4 = sys::OIDNFormat_OIDN_FORMAT_FLOAT4,
+    /// One 16-bit float channel.
+    Half = sys::OIDNFormat_OIDN_FORMAT_HALF,
+    /// Two 16-bit float channels.
+    Half2 = sys::OIDNFormat_OIDN_FORMAT_HALF2,
+    /// Three 16-bit float channels.
+    Half3 = sys::OIDNFormat_OIDN_FORMAT_HALF3,
+    /// Four 16-bit float channels.
+    Half4 = sys::OIDNFormat_OIDN_FORMAT_HALF4,
+}
+
+impl Format {
+    pub fn as_raw_oidn_format(&self) -> sys::OIDNFormat {
+        *self as sys::OIDNFormat
+    }
+
+    /// Number of channels per pixel.
+    pub fn channels(&self) -> usize {
+        match self {
+            Format::Float | Format::Half => 1,
+            Format::Float2 | Format::Half2 => 2,
+            Format::Float3 | Format::Half3 => 3,
+            Format::Float4 | Format::Half4 => 4,
+        }
+    }
+
+    /// Size of a tightly packed pixel in bytes.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Format::Float | Format::Float2 | Format::Float3 | Format::Float4 => 4 * self.channels(),
+            _ => 2 * self.channels(),
+        }
+    }
+
+    /// The 32-bit float format with `channels` channels, or [None] if
+    /// `channels` is not in `1..=4`.
+    pub fn float(channels: usize) -> Option<Format> {
+        match channels {
+            1 => Some(Format::Float),
+            2 => Some(Format::Float2),
+            3 => Some(Format::Float3),
+            4 => Some(Format::Float4),
+            _ => None,
+        }
+    }
+
+    /// The 16-bit float format with `channels` channels, or [None] if
+    /// `channels` is not in `1..=4`.
+    pub fn half(channels: usize) -> Option<Format> {
+        match channels {
+            1 => Some(Format::Half),
+            2 => Some(Format::Half2),
+            3 => Some(Format::Half3),
+            4 => Some(Format::Half4),
+            _ => None,
+        }
+    }
+}
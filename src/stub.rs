@@ -0,0 +1,735 @@
+//! A no-op implementation of the Open Image Denoise C API, used in place of
+//! the raw bindings when the `stub` feature is enabled so the crate can build
+//! and link without the library installed.
+//!
+//! Devices always report themselves as a CPU device, buffers live in host
+//! memory and filters copy the color image to the output image unchanged.
+//!
+//! The types and constants are those of the bindings, while the functions
+//! are plain Rust functions shadowing the bindings' declarations. They don't
+//! export any `oidn*` symbols, so they can't clash with the real library
+//! linked by another crate in the same build.
+
+// The declarations shadowed by the stub's functions are unused
+#[allow(dead_code)]
+#[path = "sys.rs"]
+mod bindings;
+
+pub use bindings::*;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// Version reported by the stub device, matching the bundled bindings.
+const STUB_VERSION: c_int = 20300;
+
+struct StubDevice {
+    refs: AtomicUsize,
+    error: Mutex<Option<(OIDNError, CString)>>,
+    last_message: Mutex<CString>,
+    error_fn: Mutex<(OIDNErrorFunction, usize)>,
+    params: Mutex<HashMap<String, c_int>>,
+}
+
+struct StubBuffer {
+    refs: AtomicUsize,
+    device: OIDNDevice,
+    // Owned host storage, empty for buffers sharing user memory
+    storage: Vec<u8>,
+    data: *mut u8,
+    size: usize,
+    kind: OIDNStorage,
+}
+
+#[derive(Clone, Copy)]
+struct StubImage {
+    buffer: OIDNBuffer,
+    data: *mut u8,
+    format: OIDNFormat,
+    width: usize,
+    height: usize,
+    byte_offset: usize,
+    pixel_stride: usize,
+    row_stride: usize,
+}
+
+struct StubFilter {
+    refs: AtomicUsize,
+    device: OIDNDevice,
+    images: HashMap<String, StubImage>,
+    params: HashMap<String, f32>,
+}
+
+thread_local! {
+    /// Error state for calls without a device, e.g. failing to create one,
+    /// and the message last returned from it.
+    static GLOBAL_ERROR: RefCell<(Option<(OIDNError, CString)>, CString)> =
+        RefCell::new((None, CString::default()));
+}
+
+unsafe fn device<'a>(device: OIDNDevice) -> &'a StubDevice {
+    &*(device as *const StubDevice)
+}
+
+unsafe fn buffer<'a>(buffer: OIDNBuffer) -> &'a mut StubBuffer {
+    &mut *(buffer as *mut StubBuffer)
+}
+
+unsafe fn filter<'a>(filter: OIDNFilter) -> &'a mut StubFilter {
+    &mut *(filter as *mut StubFilter)
+}
+
+unsafe fn name(name: *const c_char) -> String {
+    CStr::from_ptr(name).to_string_lossy().into_owned()
+}
+
+unsafe fn set_error(dev: OIDNDevice, code: OIDNError, msg: &str) {
+    let msg = CString::new(msg).unwrap();
+    if dev.is_null() {
+        GLOBAL_ERROR.with_borrow_mut(|(error, _)| {
+            error.get_or_insert((code, msg));
+        });
+        return;
+    }
+    let dev = device(dev);
+    let (func, user_ptr) = *dev.error_fn.lock().unwrap();
+    if let Some(func) = func {
+        func(user_ptr as *mut c_void, code, msg.as_ptr());
+    }
+    let mut error = dev.error.lock().unwrap();
+    if error.is_none() {
+        *error = Some((code, msg));
+    }
+}
+
+fn format_size(format: OIDNFormat) -> usize {
+    match format {
+        OIDNFormat_OIDN_FORMAT_FLOAT => 4,
+        OIDNFormat_OIDN_FORMAT_FLOAT2 => 8,
+        OIDNFormat_OIDN_FORMAT_FLOAT3 => 12,
+        OIDNFormat_OIDN_FORMAT_FLOAT4 => 16,
+        OIDNFormat_OIDN_FORMAT_HALF => 2,
+        OIDNFormat_OIDN_FORMAT_HALF2 => 4,
+        OIDNFormat_OIDN_FORMAT_HALF3 => 6,
+        OIDNFormat_OIDN_FORMAT_HALF4 => 8,
+        _ => 0,
+    }
+}
+
+pub unsafe fn oidnGetNumPhysicalDevices() -> c_int {
+    1
+}
+
+pub unsafe fn oidnGetPhysicalDeviceBool(_id: c_int, _name: *const c_char) -> bool {
+    false
+}
+
+pub unsafe fn oidnGetPhysicalDeviceInt(id: c_int, name: *const c_char) -> c_int {
+    match (id, self::name(name).as_str()) {
+        (0, "type") => OIDNDeviceType_OIDN_DEVICE_TYPE_CPU as c_int,
+        _ => 0,
+    }
+}
+
+pub unsafe fn oidnGetPhysicalDeviceString(id: c_int, name: *const c_char) -> *const c_char {
+    match (id, self::name(name).as_str()) {
+        (0, "name") => c"Stub CPU".as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+pub unsafe fn oidnGetPhysicalDeviceData(
+    _id: c_int,
+    _name: *const c_char,
+    byte_size: *mut usize,
+) -> *const c_void {
+    if !byte_size.is_null() {
+        *byte_size = 0;
+    }
+    ptr::null()
+}
+
+pub unsafe fn oidnIsCPUDeviceSupported() -> bool {
+    true
+}
+
+pub unsafe fn oidnIsCUDADeviceSupported(_device_id: c_int) -> bool {
+    false
+}
+
+pub unsafe fn oidnIsHIPDeviceSupported(_device_id: c_int) -> bool {
+    false
+}
+
+pub unsafe fn oidnIsMetalDeviceSupported(_device: MTLDevice_id) -> bool {
+    false
+}
+
+pub unsafe fn oidnNewDevice(type_: OIDNDeviceType) -> OIDNDevice {
+    match type_ {
+        OIDNDeviceType_OIDN_DEVICE_TYPE_DEFAULT | OIDNDeviceType_OIDN_DEVICE_TYPE_CPU => {
+            let dev = Box::new(StubDevice {
+                refs: AtomicUsize::new(1),
+                error: Mutex::new(None),
+                last_message: Mutex::new(CString::default()),
+                error_fn: Mutex::new((None, 0)),
+                params: Mutex::new(HashMap::new()),
+            });
+            Box::into_raw(dev) as OIDNDevice
+        }
+        _ => {
+            unsafe {
+                set_error(
+                    ptr::null_mut(),
+                    OIDNError_OIDN_ERROR_UNSUPPORTED_HARDWARE,
+                    "the stub only supports CPU devices",
+                );
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+pub unsafe fn oidnNewDeviceByID(id: c_int) -> OIDNDevice {
+    if id == 0 {
+        oidnNewDevice(OIDNDeviceType_OIDN_DEVICE_TYPE_CPU)
+    } else {
+        ptr::null_mut()
+    }
+}
+
+pub unsafe fn oidnNewDeviceByUUID(_uuid: *const c_void) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewDeviceByLUID(_luid: *const c_void) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewDeviceByPCIAddress(
+    _pci_domain: c_int,
+    _pci_bus: c_int,
+    _pci_device: c_int,
+    _pci_function: c_int,
+) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewCUDADevice(
+    _device_ids: *const c_int,
+    _streams: *const cudaStream_t,
+    _num_pairs: c_int,
+) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewHIPDevice(
+    _device_ids: *const c_int,
+    _streams: *const hipStream_t,
+    _num_pairs: c_int,
+) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewMetalDevice(
+    _command_queues: *const MTLCommandQueue_id,
+    _num_queues: c_int,
+) -> OIDNDevice {
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnRetainDevice(dev: OIDNDevice) {
+    device(dev).refs.fetch_add(1, Ordering::AcqRel);
+}
+
+pub unsafe fn oidnReleaseDevice(dev: OIDNDevice) {
+    if device(dev).refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+        drop(Box::from_raw(dev as *mut StubDevice));
+    }
+}
+
+pub unsafe fn oidnSetDeviceBool(dev: OIDNDevice, name: *const c_char, value: bool) {
+    oidnSetDeviceInt(dev, name, value as c_int);
+}
+
+pub unsafe fn oidnSetDeviceInt(dev: OIDNDevice, name: *const c_char, value: c_int) {
+    device(dev)
+        .params
+        .lock()
+        .unwrap()
+        .insert(self::name(name), value);
+}
+
+pub unsafe fn oidnGetDeviceBool(dev: OIDNDevice, name: *const c_char) -> bool {
+    oidnGetDeviceInt(dev, name) != 0
+}
+
+pub unsafe fn oidnGetDeviceInt(dev: OIDNDevice, name: *const c_char) -> c_int {
+    let name = self::name(name);
+    match name.as_str() {
+        "type" => OIDNDeviceType_OIDN_DEVICE_TYPE_CPU as c_int,
+        "version" => STUB_VERSION,
+        "versionMajor" => STUB_VERSION / 10000,
+        "versionMinor" => STUB_VERSION / 100 % 100,
+        "versionPatch" => STUB_VERSION % 100,
+        _ => device(dev)
+            .params
+            .lock()
+            .unwrap()
+            .get(&name)
+            .copied()
+            .unwrap_or(0),
+    }
+}
+
+pub unsafe fn oidnSetDeviceErrorFunction(
+    dev: OIDNDevice,
+    func: OIDNErrorFunction,
+    user_ptr: *mut c_void,
+) {
+    *device(dev).error_fn.lock().unwrap() = (func, user_ptr as usize);
+}
+
+pub unsafe fn oidnGetDeviceError(dev: OIDNDevice, out_message: *mut *const c_char) -> OIDNError {
+    if dev.is_null() {
+        return GLOBAL_ERROR.with_borrow_mut(|(error, last_message)| {
+            let (code, msg) = error
+                .take()
+                .unwrap_or((OIDNError_OIDN_ERROR_NONE, CString::default()));
+            *last_message = msg;
+            if !out_message.is_null() {
+                *out_message = last_message.as_ptr();
+            }
+            code
+        });
+    }
+    let dev = device(dev);
+    let (code, msg) = dev
+        .error
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap_or((OIDNError_OIDN_ERROR_NONE, CString::default()));
+    let mut last_message = dev.last_message.lock().unwrap();
+    *last_message = msg;
+    if !out_message.is_null() {
+        *out_message = last_message.as_ptr();
+    }
+    code
+}
+
+pub unsafe fn oidnCommitDevice(_dev: OIDNDevice) {}
+
+pub unsafe fn oidnSyncDevice(_dev: OIDNDevice) {}
+
+unsafe fn new_buffer(
+    dev: OIDNDevice,
+    storage: Vec<u8>,
+    data: *mut u8,
+    size: usize,
+    kind: OIDNStorage,
+) -> OIDNBuffer {
+    oidnRetainDevice(dev);
+    let mut buf = Box::new(StubBuffer {
+        refs: AtomicUsize::new(1),
+        device: dev,
+        storage,
+        data,
+        size,
+        kind,
+    });
+    if buf.data.is_null() {
+        buf.data = buf.storage.as_mut_ptr();
+    }
+    Box::into_raw(buf) as OIDNBuffer
+}
+
+pub unsafe fn oidnNewBuffer(dev: OIDNDevice, byte_size: usize) -> OIDNBuffer {
+    oidnNewBufferWithStorage(dev, byte_size, OIDNStorage_OIDN_STORAGE_HOST)
+}
+
+pub unsafe fn oidnNewBufferWithStorage(
+    dev: OIDNDevice,
+    byte_size: usize,
+    storage: OIDNStorage,
+) -> OIDNBuffer {
+    let kind = match storage {
+        OIDNStorage_OIDN_STORAGE_UNDEFINED => OIDNStorage_OIDN_STORAGE_HOST,
+        kind => kind,
+    };
+    new_buffer(dev, vec![0; byte_size], ptr::null_mut(), byte_size, kind)
+}
+
+pub unsafe fn oidnNewSharedBuffer(
+    dev: OIDNDevice,
+    dev_ptr: *mut c_void,
+    byte_size: usize,
+) -> OIDNBuffer {
+    new_buffer(
+        dev,
+        Vec::new(),
+        dev_ptr as *mut u8,
+        byte_size,
+        OIDNStorage_OIDN_STORAGE_HOST,
+    )
+}
+
+pub unsafe fn oidnNewSharedBufferFromFD(
+    dev: OIDNDevice,
+    _fd_type: OIDNExternalMemoryTypeFlag,
+    _fd: c_int,
+    _byte_size: usize,
+) -> OIDNBuffer {
+    set_error(
+        dev,
+        OIDNError_OIDN_ERROR_INVALID_OPERATION,
+        "external memory is not supported by the stub device",
+    );
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewSharedBufferFromWin32Handle(
+    dev: OIDNDevice,
+    _handle_type: OIDNExternalMemoryTypeFlag,
+    _handle: *mut c_void,
+    _name: *const c_void,
+    _byte_size: usize,
+) -> OIDNBuffer {
+    set_error(
+        dev,
+        OIDNError_OIDN_ERROR_INVALID_OPERATION,
+        "external memory is not supported by the stub device",
+    );
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnNewSharedBufferFromMetal(dev: OIDNDevice, _buffer: MTLBuffer_id) -> OIDNBuffer {
+    set_error(
+        dev,
+        OIDNError_OIDN_ERROR_INVALID_OPERATION,
+        "Metal buffers are not supported by the stub device",
+    );
+    ptr::null_mut()
+}
+
+pub unsafe fn oidnGetBufferSize(buf: OIDNBuffer) -> usize {
+    buffer(buf).size
+}
+
+pub unsafe fn oidnGetBufferStorage(buf: OIDNBuffer) -> OIDNStorage {
+    buffer(buf).kind
+}
+
+pub unsafe fn oidnGetBufferData(buf: OIDNBuffer) -> *mut c_void {
+    buffer(buf).data as *mut c_void
+}
+
+pub unsafe fn oidnReadBuffer(
+    buf: OIDNBuffer,
+    byte_offset: usize,
+    byte_size: usize,
+    dst_host_ptr: *mut c_void,
+) {
+    let buf = buffer(buf);
+    if byte_offset + byte_size > buf.size {
+        set_error(
+            buf.device,
+            OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
+            "buffer region is out of bounds",
+        );
+        return;
+    }
+    ptr::copy(
+        buf.data.add(byte_offset),
+        dst_host_ptr as *mut u8,
+        byte_size,
+    );
+}
+
+pub unsafe fn oidnReadBufferAsync(
+    buf: OIDNBuffer,
+    byte_offset: usize,
+    byte_size: usize,
+    dst_host_ptr: *mut c_void,
+) {
+    oidnReadBuffer(buf, byte_offset, byte_size, dst_host_ptr);
+}
+
+pub unsafe fn oidnWriteBuffer(
+    buf: OIDNBuffer,
+    byte_offset: usize,
+    byte_size: usize,
+    src_host_ptr: *const c_void,
+) {
+    let buf = buffer(buf);
+    if byte_offset + byte_size > buf.size {
+        set_error(
+            buf.device,
+            OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
+            "buffer region is out of bounds",
+        );
+        return;
+    }
+    ptr::copy(
+        src_host_ptr as *const u8,
+        buf.data.add(byte_offset),
+        byte_size,
+    );
+}
+
+pub unsafe fn oidnWriteBufferAsync(
+    buf: OIDNBuffer,
+    byte_offset: usize,
+    byte_size: usize,
+    src_host_ptr: *const c_void,
+) {
+    oidnWriteBuffer(buf, byte_offset, byte_size, src_host_ptr);
+}
+
+pub unsafe fn oidnRetainBuffer(buf: OIDNBuffer) {
+    buffer(buf).refs.fetch_add(1, Ordering::AcqRel);
+}
+
+pub unsafe fn oidnReleaseBuffer(buf: OIDNBuffer) {
+    if buffer(buf).refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+        let buf = Box::from_raw(buf as *mut StubBuffer);
+        oidnReleaseDevice(buf.device);
+    }
+}
+
+pub unsafe fn oidnNewFilter(dev: OIDNDevice, type_: *const c_char) -> OIDNFilter {
+    if name(type_) != "RT" && name(type_) != "RTLightmap" {
+        set_error(
+            dev,
+            OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
+            "unknown filter type",
+        );
+        return ptr::null_mut();
+    }
+    oidnRetainDevice(dev);
+    let filter = Box::new(StubFilter {
+        refs: AtomicUsize::new(1),
+        device: dev,
+        images: HashMap::new(),
+        params: HashMap::new(),
+    });
+    Box::into_raw(filter) as OIDNFilter
+}
+
+pub unsafe fn oidnRetainFilter(flt: OIDNFilter) {
+    filter(flt).refs.fetch_add(1, Ordering::AcqRel);
+}
+
+pub unsafe fn oidnReleaseFilter(flt: OIDNFilter) {
+    if filter(flt).refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+        let flt = Box::from_raw(flt as *mut StubFilter);
+        for image in flt.images.values() {
+            if !image.buffer.is_null() {
+                oidnReleaseBuffer(image.buffer);
+            }
+        }
+        oidnReleaseDevice(flt.device);
+    }
+}
+
+unsafe fn set_image(flt: OIDNFilter, name: *const c_char, image: StubImage) {
+    let flt = filter(flt);
+    if !image.buffer.is_null() {
+        oidnRetainBuffer(image.buffer);
+    }
+    if let Some(old) = flt.images.insert(self::name(name), image) {
+        if !old.buffer.is_null() {
+            oidnReleaseBuffer(old.buffer);
+        }
+    }
+}
+
+pub unsafe fn oidnSetFilterImage(
+    flt: OIDNFilter,
+    name: *const c_char,
+    buf: OIDNBuffer,
+    format: OIDNFormat,
+    width: usize,
+    height: usize,
+    byte_offset: usize,
+    pixel_byte_stride: usize,
+    row_byte_stride: usize,
+) {
+    let image = StubImage {
+        buffer: buf,
+        data: buffer(buf).data,
+        format,
+        width,
+        height,
+        byte_offset,
+        pixel_stride: pixel_byte_stride,
+        row_stride: row_byte_stride,
+    };
+    set_image(flt, name, image);
+}
+
+pub unsafe fn oidnSetSharedFilterImage(
+    flt: OIDNFilter,
+    name: *const c_char,
+    dev_ptr: *mut c_void,
+    format: OIDNFormat,
+    width: usize,
+    height: usize,
+    byte_offset: usize,
+    pixel_byte_stride: usize,
+    row_byte_stride: usize,
+) {
+    let image = StubImage {
+        buffer: ptr::null_mut(),
+        data: dev_ptr as *mut u8,
+        format,
+        width,
+        height,
+        byte_offset,
+        pixel_stride: pixel_byte_stride,
+        row_stride: row_byte_stride,
+    };
+    set_image(flt, name, image);
+}
+
+pub unsafe fn oidnUnsetFilterImage(flt: OIDNFilter, name: *const c_char) {
+    if let Some(old) = filter(flt).images.remove(&self::name(name)) {
+        if !old.buffer.is_null() {
+            oidnReleaseBuffer(old.buffer);
+        }
+    }
+}
+
+pub unsafe fn oidnSetSharedFilterData(
+    _flt: OIDNFilter,
+    _name: *const c_char,
+    _host_ptr: *mut c_void,
+    _byte_size: usize,
+) {
+}
+
+pub unsafe fn oidnUpdateFilterData(_flt: OIDNFilter, _name: *const c_char) {}
+
+pub unsafe fn oidnUnsetFilterData(_flt: OIDNFilter, _name: *const c_char) {}
+
+pub unsafe fn oidnSetFilterBool(flt: OIDNFilter, name: *const c_char, value: bool) {
+    oidnSetFilterFloat(flt, name, value as i32 as f32);
+}
+
+pub unsafe fn oidnGetFilterBool(flt: OIDNFilter, name: *const c_char) -> bool {
+    oidnGetFilterFloat(flt, name) != 0.0
+}
+
+pub unsafe fn oidnSetFilterInt(flt: OIDNFilter, name: *const c_char, value: c_int) {
+    oidnSetFilterFloat(flt, name, value as f32);
+}
+
+pub unsafe fn oidnGetFilterInt(flt: OIDNFilter, name: *const c_char) -> c_int {
+    oidnGetFilterFloat(flt, name) as c_int
+}
+
+pub unsafe fn oidnSetFilterFloat(flt: OIDNFilter, name: *const c_char, value: f32) {
+    filter(flt).params.insert(self::name(name), value);
+}
+
+pub unsafe fn oidnGetFilterFloat(flt: OIDNFilter, name: *const c_char) -> f32 {
+    let name = self::name(name);
+    // Like OIDN, an unset input scale reads back as NaN, meaning it is
+    // computed implicitly
+    let default = if name == "inputScale" { f32::NAN } else { 0.0 };
+    filter(flt).params.get(&name).copied().unwrap_or(default)
+}
+
+/// Filtering is a copy which can't be interrupted, so progress is never
+/// reported and filters can't be canceled.
+pub unsafe fn oidnSetFilterProgressMonitorFunction(
+    _flt: OIDNFilter,
+    _func: OIDNProgressMonitorFunction,
+    _user_ptr: *mut c_void,
+) {
+}
+
+pub unsafe fn oidnCommitFilter(_flt: OIDNFilter) {}
+
+pub unsafe fn oidnExecuteFilter(flt: OIDNFilter) {
+    let flt = filter(flt);
+    // Without a color image a single auxiliary image is being prefiltered
+    let input = ["color", "albedo", "normal"]
+        .iter()
+        .find_map(|name| flt.images.get(*name));
+    let (Some(color), Some(output)) = (input, flt.images.get("output")) else {
+        set_error(
+            flt.device,
+            OIDNError_OIDN_ERROR_INVALID_OPERATION,
+            "an input and the output image must be set",
+        );
+        return;
+    };
+    if color.format != output.format || color.width != output.width || color.height != output.height
+    {
+        set_error(
+            flt.device,
+            OIDNError_OIDN_ERROR_INVALID_OPERATION,
+            "color and output images must have the same format and dimensions",
+        );
+        return;
+    }
+    // Like OIDN, reject auxiliary images of another size than the output
+    let aux_mismatch = ["albedo", "normal"]
+        .iter()
+        .filter_map(|name| flt.images.get(*name))
+        .any(|aux| aux.width != output.width || aux.height != output.height);
+    if aux_mismatch {
+        set_error(
+            flt.device,
+            OIDNError_OIDN_ERROR_INVALID_OPERATION,
+            "image size mismatch",
+        );
+        return;
+    }
+    let pixel_size = format_size(color.format);
+    let strides = |image: &StubImage| {
+        let pixel_stride = match image.pixel_stride {
+            0 => pixel_size,
+            stride => stride,
+        };
+        let row_stride = match image.row_stride {
+            0 => pixel_stride * image.width,
+            stride => stride,
+        };
+        (pixel_stride, row_stride)
+    };
+    let (color_pixel, color_row) = strides(color);
+    let (output_pixel, output_row) = strides(output);
+    if color_row < color.width * color_pixel || output_row < output.width * output_pixel {
+        set_error(
+            flt.device,
+            OIDNError_OIDN_ERROR_INVALID_ARGUMENT,
+            "row stride smaller than width * pixel stride",
+        );
+        return;
+    }
+    for y in 0..color.height {
+        for x in 0..color.width {
+            let src = color
+                .data
+                .add(color.byte_offset + y * color_row + x * color_pixel);
+            let dst = output
+                .data
+                .add(output.byte_offset + y * output_row + x * output_pixel);
+            ptr::copy(src, dst, pixel_size);
+        }
+    }
+}
+
+pub unsafe fn oidnExecuteFilterAsync(flt: OIDNFilter) {
+    oidnExecuteFilter(flt);
+}
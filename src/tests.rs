@@ -32,6 +32,7 @@ fn buffer_import_read_write() {
         return;
     }
     let mut buffer = unsafe { device.create_buffer_from_raw(raw_buffer) };
+    assert_eq!(buffer.byte_size(), mem::size_of::<f32>());
     buffer.write(&[1.0]).unwrap();
     assert_eq!(buffer.read(), vec![1.0]);
     let mut slice = vec![0.0];
@@ -41,3 +42,1379 @@ fn buffer_import_read_write() {
         panic!("test failed with {err:?}: {str}")
     }
 }
+
+#[cfg(test)]
+#[test]
+fn cleared_albedo_matches_no_albedo() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = |height: usize| -> Vec<f32> {
+        (0..3 * WIDTH * height)
+            .map(|i| (i % 7) as f32 / 7.0)
+            .collect()
+    };
+
+    let mut filter = crate::RayTracing::new(&device);
+    let mut output = vec![0.0; 3 * WIDTH * HEIGHT];
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo(&[0.5; 3 * WIDTH * HEIGHT])
+        .filter(&color(HEIGHT), &mut output)
+        .unwrap();
+    // Changing the dimensions drops the albedo, which must then be unbound
+    // from the filter as well: OIDN rejects an albedo of another size than
+    // the color, and would use it to denoise otherwise
+    let mut output = vec![0.0; 3 * WIDTH * 2 * HEIGHT];
+    filter
+        .image_dimensions(WIDTH, 2 * HEIGHT)
+        .filter(&color(2 * HEIGHT), &mut output)
+        .unwrap();
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+
+    let mut expected = vec![0.0; output.len()];
+    crate::RayTracing::new(&device)
+        .image_dimensions(WIDTH, 2 * HEIGHT)
+        .filter(&color(2 * HEIGHT), &mut expected)
+        .unwrap();
+    assert_eq!(output, expected);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn hdr_implicit_input_scale() {
+    const WIDTH: usize = 8;
+    const HEIGHT: usize = 8;
+    let device = crate::Device::new();
+    let color: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| (i % 5) as f32 * 10.0)
+        .collect();
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .hdr(true)
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter(&color, &mut output)
+        .unwrap();
+    // OIDN reports the scale as NaN while it computes it implicitly
+    assert!(filter.get_float("inputScale").is_nan());
+    assert!(output.iter().all(|x| x.is_finite()));
+    filter.input_scale(2.0).filter(&color, &mut output).unwrap();
+    assert_eq!(filter.get_float("inputScale"), 2.0);
+    // Going back to the implicit scale must not keep the explicit one bound
+    filter
+        .reset()
+        .hdr(true)
+        .filter(&color, &mut output)
+        .unwrap();
+    assert!(filter.get_float("inputScale").is_nan());
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn duplicate_config_on_other_device() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let other = crate::Device::new();
+    let color: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| (i % 3) as f32 / 3.0)
+        .collect();
+
+    let mut template = crate::RayTracing::new(&device);
+    template
+        .srgb(true)
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo(&[0.5; 3 * WIDTH * HEIGHT]);
+    let mut expected = vec![0.0; color.len()];
+    template.filter(&color, &mut expected).unwrap();
+
+    let mut output = vec![0.0; color.len()];
+    template
+        .duplicate_config(&other)
+        .filter(&color, &mut output)
+        .unwrap();
+    assert_eq!(output, expected);
+    for device in [&device, &other] {
+        if let Err((err, str)) = device.get_error() {
+            panic!("test failed with {err:?}: {str}")
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn filter_unknown_type() {
+    let device = crate::Device::new();
+    assert!(crate::Filter::new(&device, "RT").is_ok());
+    assert_eq!(
+        crate::Filter::new(&device, "NotAFilter").unwrap_err(),
+        crate::Error::InvalidArgument
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn frame_denoiser_matches_filter() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.srgb(true).image_dimensions(WIDTH, HEIGHT);
+    let mut template = crate::RayTracing::new(&device);
+    template.srgb(true).image_dimensions(WIDTH, HEIGHT);
+    let mut denoiser = crate::FrameDenoiser::new(template).unwrap();
+    for frame in 0..3 {
+        let color: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+            .map(|i| ((i + frame) % 4) as f32 / 4.0)
+            .collect();
+        let mut expected = vec![0.0; color.len()];
+        filter.filter(&color, &mut expected).unwrap();
+        let mut output = vec![0.0; color.len()];
+        denoiser.denoise_into(&color, &mut output).unwrap();
+        assert_eq!(output, expected);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn frame_denoiser_auto_input_scale_per_frame() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .hdr(true)
+        .auto_input_scale_percentile(50.0)
+        .image_dimensions(WIDTH, HEIGHT);
+    let mut template = crate::RayTracing::new(&device);
+    template
+        .hdr(true)
+        .auto_input_scale_percentile(50.0)
+        .image_dimensions(WIDTH, HEIGHT);
+    let mut denoiser = crate::FrameDenoiser::new(template).unwrap();
+    for brightness in [1.0, 4.0] {
+        let color = vec![brightness; 3 * WIDTH * HEIGHT];
+        let mut output = vec![0.0; color.len()];
+        filter.filter(&color, &mut output).unwrap();
+        denoiser.denoise_into(&color, &mut output).unwrap();
+        let scale = denoiser.filter().get_float("inputScale");
+        assert!(scale.is_finite());
+        assert_eq!(scale, filter.get_float("inputScale"));
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn grayscale_with_albedo() {
+    const WIDTH: usize = 6;
+    const HEIGHT: usize = 5;
+    let device = crate::Device::new();
+    let color: Vec<f32> = (0..WIDTH * HEIGHT).map(|i| (i % 4) as f32 / 4.0).collect();
+    let mut output = vec![0.0; color.len()];
+    crate::RayTracing::new(&device)
+        .image_dimensions_channels(WIDTH, HEIGHT, 1)
+        .albedo(&[0.5; 3 * WIDTH * HEIGHT])
+        .filter(&color, &mut output)
+        .unwrap();
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+    if device.is_stub() {
+        assert_eq!(output, color);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn parse_quality_and_device_type() {
+    assert_eq!("High".parse(), Ok(crate::Quality::High));
+    assert_eq!(crate::Quality::try_from("fast"), Ok(crate::Quality::Fast));
+    assert_eq!("CUDA".parse(), Ok(crate::DeviceType::Cuda));
+    let err = "vulkan".parse::<crate::DeviceType>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown value \"vulkan\", expected one of: default, cpu, sycl, cuda, hip, metal"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn query_committed_filter_params() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .hdr(true)
+        .input_scale(0.5)
+        .filter_quality(crate::Quality::High)
+        .max_memory_mb(Some(512))
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter(&color, &mut output)
+        .unwrap();
+    assert!(filter.get_bool("hdr"));
+    assert_eq!(filter.get_int("maxMemoryMB"), 512);
+    assert_eq!(filter.get_float("inputScale"), 0.5);
+    assert_eq!(
+        filter.get_int("quality"),
+        crate::Quality::High.as_raw_oidn_quality() as i32
+    );
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn sync_device_across_threads() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = std::sync::Arc::new(crate::SyncDevice::new(crate::Device::new()));
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let device = device.clone();
+            std::thread::spawn(move || {
+                device.with(|device| {
+                    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+                    let mut output = vec![0.0; color.len()];
+                    crate::RayTracing::new(device)
+                        .image_dimensions(WIDTH, HEIGHT)
+                        .filter(&color, &mut output)
+                        .unwrap();
+                    device.get_error()
+                })
+            })
+        })
+        .collect();
+    for thread in threads {
+        if let Err((err, str)) = thread.join().unwrap() {
+            panic!("test failed with {err:?}: {str}")
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn empty_image_dimensions() {
+    let device = crate::Device::new();
+    let mut output = vec![];
+    for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+        let mut filter = crate::RayTracing::new(&device);
+        filter.image_dimensions(width, height);
+        assert_eq!(
+            filter.filter(&[], &mut output),
+            Err(crate::Error::InvalidImageDimensions)
+        );
+        assert!(filter.get_error().is_err());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn single_row_and_column_images() {
+    let device = crate::Device::new();
+    for (width, height) in [(1, 1), (1, 9), (128, 1)] {
+        let color: Vec<f32> = (0..3 * width * height)
+            .map(|i| (i % 3) as f32 / 3.0)
+            .collect();
+        let mut output = vec![0.0; color.len()];
+        crate::RayTracing::new(&device)
+            .image_dimensions(width, height)
+            .albedo_normal(&color, &color)
+            .filter(&color, &mut output)
+            .unwrap();
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_index() {
+    let device = crate::Device::cpu();
+    let buffer = match device.create_buffer(&[1.0, 2.0, 3.0, 4.0]) {
+        Some(buffer) => buffer,
+        None => {
+            eprintln!("Test skipped due to buffer creation failing");
+            return;
+        }
+    };
+    assert_eq!(buffer.len(), 4);
+    assert!(!buffer.is_empty());
+    if let Ok(buffer) = buffer.into_host() {
+        assert_eq!(buffer[1], 2.0);
+        assert_eq!(&buffer[2..], &[3.0, 4.0]);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn new_checked_device() {
+    if let Err((err, str)) = crate::Device::new_checked() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn filter_to_srgb_quantizes() {
+    let device = crate::Device::new();
+    let color = [0.0, 0.5, 1.0, 2.0, -1.0, 0.2];
+    let mut output = [0u8; 6];
+    crate::RayTracing::new(&device)
+        .image_dimensions(2, 1)
+        .filter_to_srgb(&color, &mut output)
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(output, [0, 188, 255, 255, 0, 124]);
+    }
+    crate::RayTracing::new(&device)
+        .image_dimensions(2, 1)
+        .filter_to_u8(&color, &mut output, false)
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(output, [0, 128, 255, 255, 0, 51]);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn prefilter_aux_exposes_cleaned_images() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let albedo = vec![0.25; 3 * WIDTH * HEIGHT];
+    let normal = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo_normal(&albedo, &normal);
+    assert!(filter.cleaned_albedo().is_none());
+    filter.prefilter_aux().unwrap();
+    let cleaned_albedo = filter.cleaned_albedo().unwrap().read();
+    let cleaned_normal = filter.cleaned_normal().unwrap().read();
+    if device.is_stub() {
+        assert_eq!(cleaned_albedo, albedo);
+        assert_eq!(cleaned_normal, normal);
+    }
+
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    filter.filter(&color, &mut output).unwrap();
+    // Setting new auxiliary images discards the cleaned ones
+    filter.albedo(&albedo);
+    assert!(filter.cleaned_albedo().is_none());
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(all(test, feature = "image"))]
+#[test]
+fn filter_dynamic_keeps_color_type() {
+    let device = crate::Device::new();
+    let pixels: Vec<u8> = (0..4 * 5 * 3).map(|i| (i * 13 % 256) as u8).collect();
+    let image = image::RgbaImage::from_raw(5, 3, pixels).unwrap();
+    let image = image::DynamicImage::ImageRgba8(image);
+    // A region set for another image size doesn't apply to the dynamic image
+    let denoised = crate::RayTracing::new(&device)
+        .image_dimensions(64, 64)
+        .region(32, 32, 32, 32)
+        .filter_dynamic(&image)
+        .unwrap();
+    assert_eq!(denoised.color(), image::ColorType::Rgba8);
+    assert_eq!((denoised.width(), denoised.height()), (5, 3));
+    if device.is_stub() {
+        assert_eq!(denoised, image);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_copy_from() {
+    let device = crate::Device::new();
+    let (Some(src), Some(mut dst)) = (
+        device.create_buffer(&[1.0, 2.0, 3.0, 4.0]),
+        device.create_buffer(&[0.0; 4]),
+    ) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    dst.copy_from(&src, 1, 2, 2).unwrap();
+    assert_eq!(dst.read(), vec![0.0, 3.0, 4.0, 0.0]);
+    assert_eq!(
+        dst.copy_from(&src, 3, 0, 2),
+        Err(crate::Error::InvalidArgument)
+    );
+    let other = crate::Device::new();
+    let foreign = other.create_buffer(&[0.0; 4]).unwrap();
+    assert_eq!(
+        dst.copy_from(&foreign, 0, 0, 1),
+        Err(crate::Error::InvalidArgument)
+    );
+}
+
+#[cfg(all(test, feature = "half"))]
+#[test]
+fn half_precision_filter() {
+    use half::f16;
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color: Vec<f16> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| f16::from_f32((i % 4) as f32 / 4.0))
+        .collect();
+    let mut output = vec![f16::ZERO; color.len()];
+    let mut filter = crate::RayTracing::<f16>::new_typed(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo(&[f16::from_f32(0.5); 3 * WIDTH * HEIGHT])
+        .filter(&color, &mut output)
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(output, color);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn fast_quality_falls_back_on_old_versions() {
+    use crate::Quality;
+    const WIDTH: usize = 2;
+    const HEIGHT: usize = 2;
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter_quality(Quality::Fast)
+        .filter(&color, &mut output)
+        .unwrap();
+    // The fast quality level was added in OIDN 2.3
+    let expected = if device.version() >= 20300 {
+        Quality::Fast
+    } else {
+        Quality::Balanced
+    };
+    assert_eq!(
+        filter.get_int("quality"),
+        expected.as_raw_oidn_quality() as i32
+    );
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_pixels() {
+    let device = crate::Device::new();
+    let Some(buffer) = device.create_buffer(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert_eq!(
+        buffer.read_pixels::<3>(),
+        vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+    );
+    if let Some(pixels) = buffer.pixels(3) {
+        assert_eq!(
+            pixels.collect::<Vec<_>>(),
+            [&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]]
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn device_from_raw_retains() {
+    let raw =
+        unsafe { crate::sys::oidnNewDevice(crate::sys::OIDNDeviceType_OIDN_DEVICE_TYPE_DEFAULT) };
+    if raw.is_null() {
+        eprintln!("Test skipped due to device creation failing");
+        return;
+    }
+    unsafe { crate::sys::oidnCommitDevice(raw) };
+    drop(unsafe { crate::Device::from_raw(raw) });
+    // The handle must still be usable after the wrapping device is dropped
+    let device = unsafe { crate::Device::from_raw(raw) };
+    unsafe { crate::sys::oidnReleaseDevice(raw) };
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn owned_buffer_filter() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let contents = vec![0.5; 3 * WIDTH * HEIGHT];
+    let Some(color) = device.create_buffer_owned(contents.clone()) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert_eq!(color.read(), contents);
+    let mut output = device.create_buffer(&vec![0.0; contents.len()]).unwrap();
+    crate::RayTracing::new(&device)
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter_buffer(&color, &mut output)
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(output.read(), contents);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn filter_without_image_dimensions() {
+    let device = crate::Device::new();
+    let mut color = vec![0.5; 3 * 4 * 4];
+    let filter = crate::RayTracing::new(&device);
+    assert_eq!(
+        filter.filter_in_place(&mut color),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    assert!(msg.contains("image_dimensions()"), "{msg}");
+}
+
+#[cfg(test)]
+#[test]
+fn filter_in_place_square_image() {
+    const SIZE: usize = 128;
+    let device = crate::Device::new();
+    let mut color = vec![0.5; 3 * SIZE * SIZE];
+    crate::RayTracing::new(&device)
+        .image_dimensions(SIZE, SIZE)
+        .filter_in_place(&mut color)
+        .unwrap();
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn device_builder_num_threads() {
+    let Some(builder) = crate::DeviceBuilder::new(crate::DeviceType::Cpu) else {
+        eprintln!("Test skipped due to the CPU device not being available");
+        return;
+    };
+    let device = builder.num_threads(2).set_affinity(false).commit();
+    assert_eq!(device.num_threads(), Some(2));
+    // Only the requested number is reported, not the one picked by OIDN
+    assert_eq!(crate::Device::cpu().num_threads(), None);
+    assert!(!device.get_bool("setAffinity"));
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_read_rect() {
+    let device = crate::Device::new();
+    // A 4x3 image with 2 channels, each pixel storing its (x, y)
+    let contents: Vec<f32> = (0..3)
+        .flat_map(|y| (0..4).flat_map(move |x| [x as f32, y as f32]))
+        .collect();
+    let Some(buffer) = device.create_buffer(&contents) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert_eq!(
+        buffer.read_rect(1, 1, 2, 2, 4, 2),
+        Some(vec![1.0, 1.0, 2.0, 1.0, 1.0, 2.0, 2.0, 2.0])
+    );
+    assert_eq!(buffer.read_rect(3, 0, 2, 1, 4, 2), None);
+    assert_eq!(buffer.read_rect(0, 2, 1, 2, 4, 2), None);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn commit_then_execute() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let contents = vec![0.5; 3 * WIDTH * HEIGHT];
+    let Some(color) = device.create_buffer(&contents) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let mut output = device.create_buffer(&vec![0.0; contents.len()]).unwrap();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    filter.commit(&color, &output).unwrap();
+    filter.execute(&color, &mut output).unwrap();
+    // Changing a setting after committing must commit again before executing
+    filter.hdr(true);
+    filter.execute(&color, &mut output).unwrap();
+    assert!(filter.get_bool("hdr"));
+    if device.is_stub() {
+        assert_eq!(output.read(), contents);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_fill() {
+    let device = crate::Device::new();
+    for storage in [crate::Storage::Host, crate::Storage::Device] {
+        let Some(mut buffer) = device.create_buffer_with_storage(&[1.0, 2.0, 3.0], storage) else {
+            // Not every device supports every storage mode
+            let _ = device.get_error();
+            continue;
+        };
+        buffer.fill(0.5).unwrap();
+        assert_eq!(buffer.read(), [0.5; 3]);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn filter_pool_reuses_filters() {
+    let device = crate::Device::new();
+    let pool = crate::FilterPool::new(&device);
+    for (width, height) in [(4, 4), (8, 2), (4, 4)] {
+        let color = vec![0.5; 3 * width * height];
+        let mut output = vec![0.0; color.len()];
+        let mut filter = pool.get(width, height);
+        filter.hdr(true).filter(&color, &mut output).unwrap();
+    }
+    assert_eq!(pool.len(), 2);
+    let first = pool.get(4, 4);
+    let second = pool.get(4, 4);
+    assert_eq!(pool.len(), 1);
+    drop((first, second));
+    assert_eq!(pool.len(), 3);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn get_error_code_without_message() {
+    let device = crate::Device::new();
+    assert_eq!(device.get_error_code(), Ok(()));
+    let filter = crate::Filter::new(&device, "RT").unwrap();
+    // Executing without images is an invalid operation
+    filter.execute();
+    assert_eq!(device.get_error_code(), Err(crate::Error::InvalidOperation));
+    assert_eq!(device.get_error_code(), Ok(()));
+}
+
+#[cfg(test)]
+#[test]
+fn filter_packed_buffer_views() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    const LEN: usize = 3 * WIDTH * HEIGHT;
+    let device = crate::Device::new();
+    // Albedo, normal and color packed into a single buffer
+    let mut packed = vec![0.5; LEN];
+    packed.extend(vec![1.0; LEN]);
+    packed.extend(vec![0.25; LEN]);
+    let Some(buffer) = device.create_buffer(&packed) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert!(buffer.view(2 * LEN, LEN + 1).is_none());
+    let albedo = buffer.view(0, LEN).unwrap();
+    let normal = buffer.view(LEN, LEN).unwrap();
+    let color = buffer.view(2 * LEN, LEN).unwrap();
+    assert_eq!(albedo.read(), vec![0.5; LEN]);
+    let mut output = device.create_buffer(&[0.0; LEN]).unwrap();
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo_normal_view(albedo, normal)
+        .unwrap();
+    filter.filter_view(color, &mut output).unwrap();
+    if device.is_stub() {
+        assert_eq!(output.read(), vec![0.25; LEN]);
+    }
+    assert_eq!(
+        filter.filter_view(buffer.view(0, LEN - 3).unwrap(), &mut output),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    assert!(msg.starts_with("color has 45 elements"), "{msg}");
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn reserve_scratch_commits() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .max_memory_mb(Some(2));
+    filter.reserve_scratch().unwrap();
+    assert_eq!(filter.get_int("maxMemoryMB"), 2);
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    filter.filter(&color, &mut output).unwrap();
+
+    // An explicit commit is kept
+    let (Some(color), Some(mut output)) =
+        (device.create_buffer(&color), device.create_buffer(&output))
+    else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    filter.commit(&color, &output).unwrap();
+    filter.reserve_scratch().unwrap();
+    filter.execute(&color, &mut output).unwrap();
+    assert_eq!(filter.get_int("maxMemoryMB"), 2);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_dimensions_checked_by_filter() {
+    let device = crate::Device::new();
+    let contents = vec![0.5; 3 * 4 * 2];
+    assert!(device.create_image_buffer(&contents, 4, 4, 3).is_none());
+    let Some(color) = device.create_image_buffer(&contents, 4, 2, 3) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert_eq!(color.dimensions(), Some((4, 2, 3)));
+    // Same number of elements, but transposed
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(2, 4);
+    assert_eq!(
+        filter.denoise_buffer(&color).unwrap_err(),
+        crate::Error::InvalidImageDimensions
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    assert!(msg.contains("4x2"), "{msg}");
+    filter.image_dimensions(4, 2);
+    let output = filter.denoise_buffer(&color).unwrap();
+    assert_eq!(output.dimensions(), Some((4, 2, 3)));
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn filter_u16_round_trips() {
+    let device = crate::Device::new();
+    let color = [0, 1, 32768, 65535, 12345, 54321];
+    let mut output = [0u16; 6];
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(2, 1);
+    for srgb in [false, true] {
+        filter.filter_u16(&color, &mut output, srgb).unwrap();
+        if device.is_stub() {
+            assert_eq!(output, color);
+        }
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+    // The copy of the filter used to override srgb keeps the cancel token
+    let token = crate::CancelToken::new();
+    filter.set_cancel_token(token.clone());
+    token.cancel();
+    assert_eq!(
+        filter.filter_u16(&color, &mut output, true),
+        Err(crate::Error::Canceled)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn filter_with_timeout_cancels() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    filter
+        .filter_with_timeout(&color, &mut output, std::time::Duration::from_secs(60))
+        .unwrap();
+    // The stub never reports progress, so it can't be canceled
+    if !device.is_stub() {
+        assert_eq!(
+            filter.filter_with_timeout(&color, &mut output, std::time::Duration::ZERO),
+            Err(crate::Error::Canceled)
+        );
+        assert_eq!(device.get_error().unwrap_err().0, crate::Error::Canceled);
+        // The timeout only applies to the call it was passed to
+        filter.filter(&color, &mut output).unwrap();
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn host_buffer_deref() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let contents = vec![0.5; 3 * WIDTH * HEIGHT];
+    let Some(color) = device.create_buffer(&contents) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let output = device.create_buffer(&vec![0.0; contents.len()]).unwrap();
+    let Ok(mut output) = output.into_host() else {
+        eprintln!("Test skipped due to the buffer not being host accessible");
+        return;
+    };
+    output[0] = 1.0;
+    assert_eq!(output.buffer().read()[0], 1.0);
+    crate::RayTracing::new(&device)
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter_buffer(&color, output.buffer_mut())
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(&output[..], &contents[..]);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn albedo_set_before_new_dimensions() {
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(4, 4);
+    // Reusing the filter for another resolution, the albedo is only checked
+    // against the new dimensions when filtering
+    let color = vec![0.5; 3 * 8 * 8];
+    let mut output = vec![0.0; color.len()];
+    filter
+        .albedo(&color)
+        .image_dimensions(8, 8)
+        .filter(&color, &mut output)
+        .unwrap();
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn normal_must_have_three_channels() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo_normal(&color, &[0.0; 4 * WIDTH * HEIGHT]);
+    assert_eq!(
+        filter.filter(&color, &mut output),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    assert!(msg.starts_with("normal has 64 elements"), "{msg}");
+}
+
+#[cfg(test)]
+#[test]
+fn require_type_names_both_types() {
+    use crate::DeviceType;
+    let device = crate::Device::cpu();
+    let device = device.require_type(DeviceType::Cpu).unwrap();
+    let Err((err, msg)) = device.require_type(DeviceType::Cuda) else {
+        panic!("a CPU device was accepted as a CUDA device");
+    };
+    assert_eq!(err, crate::Error::UnsupportedHardware);
+    assert!(msg.contains("Cuda") && msg.contains("Cpu"), "{msg}");
+}
+
+#[cfg(test)]
+#[test]
+fn default_backend_matches_new_device() {
+    if std::env::var_os("OIDN_DEFAULT_DEVICE").is_some() {
+        return;
+    }
+    let device = crate::Device::new();
+    let backend = crate::Device::default_backend();
+    if backend != crate::DeviceType::Default {
+        assert_eq!(backend, device.device_type());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn albedo_accepts_slices_and_buffers() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let albedo = vec![0.25; color.len()];
+    let Some(albedo_buffer) = device.create_buffer(&albedo) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+
+    let mut expected = vec![0.0; color.len()];
+    crate::RayTracing::new(&device)
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo(&albedo)
+        .filter(&color, &mut expected)
+        .unwrap();
+
+    let mut output = vec![0.0; color.len()];
+    crate::RayTracing::new(&device)
+        .image_dimensions(WIDTH, HEIGHT)
+        .albedo(albedo_buffer)
+        .filter(&color, &mut output)
+        .unwrap();
+    assert_eq!(output, expected);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn auto_input_scale_ignores_fireflies() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let mut color = vec![0.5; 3 * WIDTH * HEIGHT];
+    color[..3].copy_from_slice(&[1000.0; 3]);
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .hdr(true)
+        .auto_input_scale_percentile(50.0)
+        .filter(&color, &mut output)
+        .unwrap();
+    assert!((filter.get_float("inputScale") - 0.18 / 0.5).abs() < 1e-5);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn foreign_buffer_error_names_devices() {
+    let device = crate::Device::new();
+    let other = crate::Device::new();
+    let Some(mut output) = other.create_buffer(&[0.0; 3]) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    assert_eq!(output.device_type(), other.device_type());
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(1, 1);
+    assert_eq!(
+        filter.filter_in_place_buffer(&mut output),
+        Err(crate::Error::InvalidArgument)
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    let expected = format!(
+        "output buffer was created on another {:?} device",
+        device.device_type()
+    );
+    assert!(msg.starts_with(&expected), "{msg}");
+}
+
+#[cfg(test)]
+#[test]
+fn filter_buffer_wide_image() {
+    // Mirrors examples/buffer: a wide image filtered between two buffers
+    const WIDTH: usize = 128;
+    const HEIGHT: usize = 9;
+    let device = crate::Device::new();
+    let input: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| (i % 11) as f32 / 11.0)
+        .collect();
+    let Some(buffer) = device.create_buffer(&input) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let mut output_buffer = device.create_buffer(&vec![0.0; input.len()]).unwrap();
+    crate::filter::RayTracing::new(&device)
+        .image_dimensions(WIDTH, HEIGHT)
+        .filter_buffer(&buffer, &mut output_buffer)
+        .unwrap();
+    let pixels = output_buffer.read_pixels::<3>();
+    assert_eq!(pixels.len(), WIDTH * HEIGHT);
+    if device.is_stub() {
+        assert_eq!(output_buffer.read(), input);
+    }
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn buffer_import_misaligned_size() {
+    let device = crate::Device::new();
+    let raw_buffer = unsafe { crate::sys::oidnNewBuffer(device.raw(), 6) };
+    if raw_buffer.is_null() {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    }
+    let imported = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        device.create_buffer_from_raw(raw_buffer)
+    }));
+    assert!(imported.is_err());
+    unsafe { crate::sys::oidnReleaseBuffer(raw_buffer) };
+}
+
+#[cfg(test)]
+#[test]
+fn region_filters_part_of_image() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color = vec![0.5; 3 * WIDTH * HEIGHT];
+    let mut output = vec![0.0; color.len()];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .region(1, 1, 2, 2)
+        .filter(&color, &mut output)
+        .unwrap();
+    for (i, pixel) in output.chunks_exact(3).enumerate() {
+        let (x, y) = (i % WIDTH, i / WIDTH);
+        if !(1..3).contains(&x) || !(1..3).contains(&y) {
+            assert_eq!(pixel, [0.0; 3], "pixel ({x}, {y}) is outside the region");
+        } else if device.is_stub() {
+            assert_eq!(pixel, [0.5; 3]);
+        }
+    }
+    // Regions reaching past the end of the address space don't fit either
+    filter.region(usize::MAX, 0, 2, 2);
+    assert_eq!(
+        filter.filter(&color, &mut output),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+    let (_, msg) = filter.get_error().unwrap_err();
+    assert!(msg.contains("does not fit"), "{msg}");
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn shared_aux_across_color_stack() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    const LEN: usize = 3 * WIDTH * HEIGHT;
+    let device = crate::Device::new();
+    let Some(albedo) = device.create_buffer(&[0.5; LEN]) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let normal = device.create_buffer(&[0.0; LEN]).unwrap();
+    let colors = [
+        device.create_buffer(&[0.25; LEN]).unwrap(),
+        device.create_buffer(&[0.75; LEN]).unwrap(),
+    ];
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .image_dimensions(WIDTH, HEIGHT)
+        .set_shared_aux(&albedo, &normal)
+        .image_dimensions(WIDTH, HEIGHT);
+    for color in &colors {
+        let mut output = device.create_buffer(&[0.0; LEN]).unwrap();
+        filter.filter_buffer(color, &mut output).unwrap();
+        if device.is_stub() {
+            assert_eq!(output.read(), color.read());
+        }
+    }
+    // Setting a slice must not write into the application's buffer
+    filter.albedo(&[1.0; LEN]);
+    assert_eq!(albedo.read(), vec![0.5; LEN]);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn peek_error_keeps_error() {
+    let device = crate::Device::new();
+    assert!(device.peek_error().is_ok());
+    let filter = crate::Filter::new(&device, "RT").unwrap();
+    // Executing without images is an invalid operation
+    filter.execute();
+    let peeked = device.peek_error();
+    assert!(peeked.is_err());
+    assert_eq!(device.peek_error(), peeked);
+    assert_eq!(device.get_error(), peeked);
+    assert!(device.peek_error().is_ok());
+    assert!(device.get_error().is_ok());
+}
+
+#[cfg(test)]
+#[test]
+fn peeked_error_stays_on_its_thread() {
+    let device = crate::Device::new();
+    let filter = crate::Filter::new(&device, "RT").unwrap();
+    filter.execute();
+    let peeked = device.peek_error();
+    assert!(peeked.is_err());
+    // Filters can be sent to other threads, which don't see the error
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let filter = filter;
+            assert!(filter.device().peek_error().is_ok());
+            assert!(filter.device().get_error().is_ok());
+            // Errors peeked by a thread which exits without reading them are
+            // discarded
+            filter.execute();
+            assert!(filter.device().peek_error().is_err());
+        })
+        .join()
+        .unwrap();
+    });
+    assert_eq!(device.get_error(), peeked);
+    assert!(device.peeked().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn filter_interleaved_gbuffer() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let color: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| (i % 5) as f32 / 5.0)
+        .collect();
+    let gbuffer: Vec<f32> = color
+        .chunks_exact(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0.5, 0.5, 0.5, 0.0, 0.0, 1.0])
+        .collect();
+    let Some(input) = device.create_buffer(&gbuffer) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let mut output = device.create_buffer(&vec![0.0; color.len()]).unwrap();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    filter
+        .filter_interleaved(
+            &input,
+            crate::InterleavedLayout::COLOR_ALBEDO_NORMAL,
+            &mut output,
+        )
+        .unwrap();
+    if device.is_stub() {
+        assert_eq!(output.read(), color);
+    }
+    let layout = crate::InterleavedLayout {
+        normal: Some(7),
+        ..crate::InterleavedLayout::COLOR_ALBEDO_NORMAL
+    };
+    assert_eq!(
+        filter.filter_interleaved(&input, layout, &mut output),
+        Err(crate::Error::InvalidArgument)
+    );
+    let mut short_output = device.create_buffer(&[0.0; 3]).unwrap();
+    assert_eq!(
+        filter.filter_interleaved(
+            &input,
+            crate::InterleavedLayout::COLOR_ALBEDO_NORMAL,
+            &mut short_output
+        ),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+    assert!(matches!(filter.get_error(), Err((_, msg)) if msg.starts_with("output")));
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn execute_async_with_callback() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let device = crate::Device::new();
+    let contents = vec![0.5; 3 * WIDTH * HEIGHT];
+    let Some(color) = device.create_buffer(&contents) else {
+        eprintln!("Test skipped due to buffer creation failing");
+        return;
+    };
+    let output_buffer = device.create_buffer(&vec![0.0; contents.len()]).unwrap();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let pending = filter
+        .execute_async_with(color, output_buffer, move |result| {
+            sender.send(result.map(|output| output.read())).unwrap();
+        })
+        .unwrap();
+    // The callback reports completion without joining the handle
+    let output = receiver.recv().unwrap().unwrap();
+    pending.join();
+    if device.is_stub() {
+        assert_eq!(output, contents);
+    }
+    // Dropping the handle waits for the callback
+    let color = device.create_buffer(&contents).unwrap();
+    let output_buffer = device.create_buffer(&vec![0.0; contents.len()]).unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    drop(
+        filter
+            .execute_async_with(color, output_buffer, move |result| {
+                sender.send(result.is_ok()).unwrap();
+            })
+            .unwrap(),
+    );
+    assert_eq!(receiver.try_recv(), Ok(true));
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(all(test, unix))]
+#[test]
+fn external_memory_unsupported_by_stub() {
+    let device = crate::Device::new();
+    if !device.is_stub() {
+        return;
+    }
+    assert!(!device.supports_external_memory(crate::ExternalMemoryType::OpaqueFd));
+    let buffer =
+        unsafe { device.create_buffer_from_fd::<f32>(crate::ExternalMemoryType::OpaqueFd, -1, 4) };
+    assert!(buffer.is_none());
+    assert_eq!(
+        device.get_error().unwrap_err().0,
+        crate::Error::InvalidOperation
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn deterministic_filter_is_reproducible() {
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    let Some(device) = crate::Device::deterministic(2) else {
+        eprintln!("Test skipped due to the CPU device not being available");
+        return;
+    };
+    assert_eq!(device.num_threads(), Some(2));
+    let color: Vec<f32> = (0..3 * WIDTH * HEIGHT)
+        .map(|i| (i % 7) as f32 / 7.0)
+        .collect();
+    let mut filter = crate::RayTracing::new(&device);
+    filter.image_dimensions(WIDTH, HEIGHT).deterministic();
+    assert!(format!("{filter:?}").contains("deterministic: true"));
+    let mut first = vec![0.0; color.len()];
+    let mut second = vec![0.0; color.len()];
+    filter.filter(&color, &mut first).unwrap();
+    filter.filter(&color, &mut second).unwrap();
+    assert_eq!(first, second);
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn new_with_fallback_ends_with_cpu() {
+    let device = crate::Device::new_with_fallback(&[]);
+    assert_eq!(device.device_type(), crate::DeviceType::Cpu);
+    let device = crate::Device::new_with_fallback(&[crate::DeviceType::Cuda]);
+    assert!(matches!(
+        device.device_type(),
+        crate::DeviceType::Cuda | crate::DeviceType::Cpu
+    ));
+    if let Err((err, str)) = device.get_error() {
+        panic!("test failed with {err:?}: {str}")
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn set_unwrapped_filter_params() {
+    let device = crate::Device::new();
+    let mut filter = crate::RayTracing::new(&device);
+    filter
+        .set_bool("directional", true)
+        .set_int("tileAlignment", 8)
+        .set_float("customScale", 0.5)
+        .set_data("customData", &[1, 2, 3]);
+    if device.is_stub() {
+        assert!(filter.get_bool("directional"));
+        assert_eq!(filter.get_int("tileAlignment"), 8);
+        assert_eq!(filter.get_float("customScale"), 0.5);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn denoise_rejects_wrong_aux_len() {
+    let color = vec![0.5; 3 * 4 * 4];
+    let opts = crate::DenoiseOptions {
+        albedo: Some(&color[..3]),
+        ..Default::default()
+    };
+    assert_eq!(
+        crate::denoise(&color, 4, 4, opts),
+        Err(crate::Error::InvalidImageDimensions)
+    );
+}